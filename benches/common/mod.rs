@@ -0,0 +1,15 @@
+//! Inputs shared by the benchmarks
+
+/// `entries` pseudo-random binary strings of `width` bits, the same on
+/// every run so timings compare like with like
+pub fn report(entries: usize, width: usize) -> Vec<String> {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    (0..entries)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            format!("{:0w$b}", state >> (64 - width), w = width)
+        })
+        .collect()
+}
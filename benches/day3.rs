@@ -0,0 +1,44 @@
+//! Day 3 part 1 on a large generated report: per-character column counting
+//! in `part1::calculate_rates` against the packed, bit-sliced counts in
+//! `packed::PackedReport`.
+//!
+//! Run with `cargo bench --bench day3`.
+mod common;
+
+use advent2021::day3::packed::PackedReport;
+use advent2021::day3::part1::calculate_rates;
+use advent2021::timing::{format_duration, measure};
+use std::hint::black_box;
+
+const ENTRIES: usize = 1_000_000;
+const WIDTH: usize = 12;
+const RUNS: usize = 5;
+
+fn main() {
+    let report = common::report(ENTRIES, WIDTH);
+    let packed = PackedReport::new(&report).unwrap();
+    assert_eq!(calculate_rates(&report), packed.rates());
+
+    let strings = measure(RUNS, || {
+        black_box(calculate_rates(black_box(&report)));
+    });
+    let packing = measure(RUNS, || {
+        black_box(PackedReport::new(black_box(&report)).unwrap());
+    });
+    let counting = measure(RUNS, || {
+        black_box(black_box(&packed).rates());
+    });
+
+    println!(
+        "{} entries of {} bits, median of {} runs",
+        ENTRIES, WIDTH, RUNS
+    );
+    println!("calculate_rates:      {}", format_duration(strings.median));
+    println!("PackedReport::new:    {}", format_duration(packing.median));
+    println!("PackedReport::rates:  {}", format_duration(counting.median));
+    println!(
+        "speedup:              {:.1}x counting, {:.1}x including packing",
+        strings.median.as_secs_f64() / counting.median.as_secs_f64(),
+        strings.median.as_secs_f64() / (packing.median + counting.median).as_secs_f64()
+    );
+}
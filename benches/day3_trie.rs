@@ -0,0 +1,122 @@
+//! Day 3 part 2 on a large generated report: the boxed `part2::BitNode`
+//! trie against the Vec-backed `arena::ArenaTrie`, comparing heap use,
+//! build time and rating time.
+//!
+//! Run with `cargo bench --bench day3_trie`.
+mod common;
+
+use advent2021::day3::arena::ArenaTrie;
+use advent2021::day3::part2::{co2_scrubber_rating, o2_generator_rating, BitNode, BitTree};
+use advent2021::timing::{format_duration, measure};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const ENTRIES: usize = 1_000_000;
+const WIDTH: usize = 32;
+const RUNS: usize = 5;
+
+/// The system allocator, counting live bytes and allocations made
+struct Counting;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Heap bytes still held and allocations made while building with `build`
+fn footprint<T>(build: impl FnOnce() -> T) -> (T, usize, usize) {
+    let (bytes, allocations) = (
+        LIVE_BYTES.load(Ordering::Relaxed),
+        ALLOCATIONS.load(Ordering::Relaxed),
+    );
+    let built = build();
+    (
+        built,
+        LIVE_BYTES.load(Ordering::Relaxed) - bytes,
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+    )
+}
+
+fn ratings<T: BitTree>(tree: &T) -> (String, String) {
+    (
+        o2_generator_rating(tree, WIDTH),
+        co2_scrubber_rating(tree, WIDTH),
+    )
+}
+
+fn main() {
+    let report = common::report(ENTRIES, WIDTH);
+    let (boxed, boxed_bytes, boxed_allocations) = footprint(|| BitNode::from(&report));
+    let (arena, arena_bytes, arena_allocations) = footprint(|| ArenaTrie::from(&report));
+    assert_eq!(ratings(&boxed), ratings(&arena));
+
+    let boxed_build = measure(RUNS, || {
+        black_box(BitNode::from(black_box(&report)));
+    });
+    let arena_build = measure(RUNS, || {
+        black_box(ArenaTrie::from(black_box(&report)));
+    });
+    let boxed_rate = measure(RUNS, || {
+        black_box(ratings(black_box(&boxed)));
+    });
+    let arena_rate = measure(RUNS, || {
+        black_box(ratings(black_box(&arena)));
+    });
+
+    let megabytes = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+    println!(
+        "{} entries of {} bits, {} trie nodes, median of {} runs",
+        ENTRIES,
+        WIDTH,
+        arena.node_count(),
+        RUNS
+    );
+    println!("            heap        allocations  build       ratings");
+    for (name, bytes, allocations, build, rate) in [
+        (
+            "boxed",
+            boxed_bytes,
+            boxed_allocations,
+            &boxed_build,
+            &boxed_rate,
+        ),
+        (
+            "arena",
+            arena_bytes,
+            arena_allocations,
+            &arena_build,
+            &arena_rate,
+        ),
+    ] {
+        println!(
+            "{:<12}{:<12}{:<13}{:<12}{}",
+            name,
+            format!("{:.1}MiB", megabytes(bytes)),
+            allocations,
+            format_duration(build.median),
+            format_duration(rate.median)
+        );
+    }
+}
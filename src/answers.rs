@@ -0,0 +1,181 @@
+//! Known-good answers, so refactors can't silently change results
+//!
+//! The answers file is TOML keyed by day and part:
+//!
+//! [day1]
+//! part1 = 1393
+//! part2 = 1359
+use crate::solution::Answer;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+pub fn default_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("answers.toml")
+}
+
+#[derive(Debug)]
+pub enum AnswersError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    BadEntry(String),
+}
+
+impl fmt::Display for AnswersError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnswersError::Io(err) => write!(f, "{}", err),
+            AnswersError::Toml(err) => write!(f, "{}", err),
+            AnswersError::BadEntry(key) => write!(f, "unexpected entry '{}'", key),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail(Answer),
+    Unknown,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Answers {
+    expected: BTreeMap<(u32, u32), Answer>,
+}
+
+impl Answers {
+    /// A missing file just means nothing has been recorded yet
+    pub fn load(path: &Path) -> Result<Answers, AnswersError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Answers::parse(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Answers::default()),
+            Err(err) => Err(AnswersError::Io(err)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Answers, AnswersError> {
+        let table = text.parse::<Table>().map_err(AnswersError::Toml)?;
+        let mut answers = Answers::default();
+
+        for (day_key, parts) in &table {
+            let day = key_number(day_key, "day")?;
+            let parts = parts
+                .as_table()
+                .ok_or_else(|| AnswersError::BadEntry(day_key.clone()))?;
+            for (part_key, value) in parts {
+                let part = key_number(part_key, "part")?;
+                let answer = match value {
+                    Value::Integer(n) => Answer::Number(*n),
+                    Value::String(s) => Answer::Text(s.clone()),
+                    _ => return Err(AnswersError::BadEntry(format!("{}.{}", day_key, part_key))),
+                };
+                answers.expected.insert((day, part), answer);
+            }
+        }
+        Ok(answers)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), AnswersError> {
+        fs::write(path, self.to_toml()).map_err(AnswersError::Io)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut result = String::new();
+        let mut current_day = None;
+        for ((day, part), answer) in &self.expected {
+            if current_day != Some(*day) {
+                if current_day.is_some() {
+                    result.push('\n');
+                }
+                result.push_str(&format!("[day{}]\n", day));
+                current_day = Some(*day);
+            }
+            let value = match answer {
+                Answer::Number(n) => Value::Integer(*n),
+                Answer::Text(s) => Value::String(s.clone()),
+            };
+            result.push_str(&format!("part{} = {}\n", part, value));
+        }
+        result
+    }
+
+    pub fn get(&self, day: u32, part: u32) -> Option<&Answer> {
+        self.expected.get(&(day, part))
+    }
+
+    pub fn record(&mut self, day: u32, part: u32, answer: Answer) {
+        self.expected.insert((day, part), answer);
+    }
+
+    pub fn check(&self, day: u32, part: u32, actual: &Answer) -> Verdict {
+        match self.get(day, part) {
+            None => Verdict::Unknown,
+            // compare as text so "1393" and 1393 in the file both match
+            Some(expected) if expected.to_string() == actual.to_string() => Verdict::Pass,
+            Some(expected) => Verdict::Fail(expected.clone()),
+        }
+    }
+}
+
+/// "day12" -> 12
+fn key_number(key: &str, prefix: &str) -> Result<u32, AnswersError> {
+    key.strip_prefix(prefix)
+        .and_then(|n| n.parse::<u32>().ok())
+        .ok_or_else(|| AnswersError::BadEntry(key.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANSWERS: &str = "[day1]\npart1 = 1393\npart2 = 1359\n\n[day3]\npart1 = \"10110\"\n";
+
+    #[test]
+    fn parses_answers_by_day_and_part() {
+        let answers = Answers::parse(ANSWERS).unwrap();
+        assert_eq!(Some(&Answer::Number(1359)), answers.get(1, 2));
+        assert_eq!(Some(&Answer::Text("10110".to_string())), answers.get(3, 1));
+        assert_eq!(None, answers.get(3, 2));
+    }
+
+    #[test]
+    fn writes_what_it_reads() {
+        let answers = Answers::parse(ANSWERS).unwrap();
+        assert_eq!(ANSWERS, answers.to_toml());
+    }
+
+    #[test]
+    fn checks_answers() {
+        let mut answers = Answers::parse(ANSWERS).unwrap();
+        assert_eq!(Verdict::Pass, answers.check(1, 1, &Answer::Number(1393)));
+        assert_eq!(
+            Verdict::Fail(Answer::Number(1359)),
+            answers.check(1, 2, &Answer::Number(1360))
+        );
+        assert_eq!(Verdict::Unknown, answers.check(2, 1, &Answer::Number(5)));
+
+        answers.record(2, 1, Answer::Number(5));
+        assert_eq!(Verdict::Pass, answers.check(2, 1, &Answer::Number(5)));
+    }
+
+    #[test]
+    fn rejects_unexpected_keys() {
+        assert!(matches!(
+            Answers::parse("[dayone]\npart1 = 1\n"),
+            Err(AnswersError::BadEntry(key)) if key == "dayone"
+        ));
+        assert!(matches!(
+            Answers::parse("[day1]\npart1 = 1.5\n"),
+            Err(AnswersError::BadEntry(key)) if key == "day1.part1"
+        ));
+    }
+
+    #[test]
+    fn treats_missing_file_as_empty() {
+        let answers = Answers::load(Path::new("no/such/answers.toml")).unwrap();
+        assert_eq!(Answers::default(), answers);
+    }
+}
@@ -0,0 +1,752 @@
+//! Command-line parsing for the puzzle runner
+//!
+//! advent2021 [run] [--all | --day N | --day FIRST..LAST] [--part P]
+//!                  [--input-dir DIR] [--input PATH | -] [--time]
+//!                  [--format text|json|csv]
+//! advent2021 bench [same selection options] [--iterations N] [--warmup N]
+//! advent2021 verify [same selection options] [--answers PATH] [--record]
+//! advent2021 fetch [--all | --day N | --day FIRST..LAST] [--input-dir DIR]
+//!                  [--base-url URL]
+//! advent2021 examples --day N --page PAGE.html [--fixtures DIR]
+//! advent2021 analyze [--input-dir DIR] [--input PATH | -] [--window N]
+//!                    [--threshold Z]
+//!
+//! With no arguments every day and part is run, as before.
+use advent2021::answers;
+use advent2021::day1::AnalyzeOptions;
+use advent2021::examples;
+use advent2021::fetch;
+use advent2021::input::{self, Source};
+use advent2021::solution;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+pub const PARTS: RangeInclusive<u32> = 1..=2;
+/// Every day of the calendar, whether or not it has a solution yet
+pub const PUZZLE_DAYS: RangeInclusive<u32> = 1..=25;
+
+pub const DEFAULT_ITERATIONS: u32 = 10;
+pub const DEFAULT_WARMUP: u32 = 2;
+
+pub const USAGE: &str = "\
+usage: advent2021 [run] [options]
+       advent2021 bench [options]
+       advent2021 verify [options]
+       advent2021 fetch [options]
+       advent2021 examples --day N --page PAGE.html [options]
+       advent2021 analyze [options]
+
+commands:
+    run                  solve the selected puzzles (the default)
+    bench                time each selected part over repeated runs
+    verify               check answers against the recorded answers file
+    fetch                download missing puzzle inputs into the input directory
+    examples             save a puzzle page's examples and answers as fixtures
+    analyze              report trends and outliers in the day 1 sonar sweep
+
+options:
+    --all                run every day (the default)
+    --day N              run a single day
+    --day FIRST..LAST    run an inclusive range of days, e.g. 3..7
+    --part P             run only part 1 or part 2
+    --input-dir DIR      read dayN_input.txt files from DIR
+                         (default: $ADVENT_INPUT_DIR, then the crate's src/)
+    --input PATH         read a single day's input from PATH, or - for stdin
+    --time               (run) report parse and solve times
+    --format FORMAT      (run) text (the default), json or csv; json and csv
+                         give one record per day and part, with timings
+    --iterations N       (bench) timed runs of each part, default 10
+    --warmup N           (bench) untimed runs before timing, default 2
+    --answers PATH       (verify) answers file, default the crate's answers.toml
+    --record             (verify) save the current answers as the expected ones
+    --base-url URL       (fetch) server to download from
+                         (default: $ADVENT_BASE_URL, then adventofcode.com)
+    --page PATH          (examples) puzzle page saved from the browser
+    --fixtures DIR       (examples) where to write fixtures, default fixtures/
+    --window N           (analyze) depths each z-score is measured against,
+                         default 10
+    --threshold Z        (analyze) report depths with a z-score beyond Z,
+                         default 3
+    --help               show this message";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(RunArgs),
+    Bench(BenchArgs),
+    Verify(VerifyArgs),
+    Fetch(FetchArgs),
+    Examples(ExamplesArgs),
+    Analyze(AnalyzeArgs),
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RunArgs {
+    pub selection: Selection,
+    pub input_dir: PathBuf,
+    /// Overrides the input directory when a single day is selected
+    pub input: Option<Source>,
+    pub time: bool,
+    pub format: Format,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BenchArgs {
+    pub run: RunArgs,
+    pub iterations: u32,
+    pub warmup: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct VerifyArgs {
+    pub run: RunArgs,
+    pub answers: PathBuf,
+    pub record: bool,
+}
+
+/// The session token is looked up only when a download is needed
+#[derive(Debug, PartialEq)]
+pub struct FetchArgs {
+    pub days: Vec<u32>,
+    pub input_dir: PathBuf,
+    pub base_url: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ExamplesArgs {
+    pub day: u32,
+    pub page: PathBuf,
+    pub fixtures_dir: PathBuf,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AnalyzeArgs {
+    pub input: Source,
+    pub options: AnalyzeOptions,
+}
+
+#[derive(PartialEq)]
+enum Kind {
+    Run,
+    Bench,
+    Verify,
+    Fetch,
+    Examples,
+    Analyze,
+}
+
+impl Kind {
+    /// Commands that run solutions, and so take part and input options
+    fn solves(&self) -> bool {
+        matches!(self, Kind::Run | Kind::Bench | Kind::Verify)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Selection {
+    pub days: Vec<u32>,
+    pub parts: Vec<u32>,
+}
+
+impl Selection {
+    pub fn all() -> Self {
+        Selection {
+            days: solution::SOLUTIONS.iter().map(|s| s.day).collect(),
+            parts: PARTS.collect(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    UnknownCommand(String),
+    UnknownOption(String),
+    MissingValue(String),
+    BadNumber(String, String),
+    Zero(String),
+    UnknownDay(u32),
+    UnknownPart(u32),
+    EmptyRange(String),
+    ConflictingOptions(String, String),
+    NeedsSingleDay(String),
+    MissingOption(String),
+    NotAPuzzleDay(u32),
+    UnknownFormat(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::UnknownCommand(cmd) => write!(f, "unknown command '{}'", cmd),
+            CliError::UnknownOption(opt) => write!(f, "unknown option '{}'", opt),
+            CliError::MissingValue(opt) => write!(f, "{} needs a value", opt),
+            CliError::BadNumber(opt, value) => {
+                write!(f, "{} expects a number, found '{}'", opt, value)
+            }
+            CliError::Zero(opt) => write!(f, "{} must be at least 1", opt),
+            CliError::UnknownDay(day) => write!(
+                f,
+                "no solution for day {} (available: {})",
+                day,
+                solution::SOLUTIONS
+                    .iter()
+                    .map(|s| s.day.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            CliError::UnknownPart(part) => write!(
+                f,
+                "no part {} (available: {}..{})",
+                part,
+                PARTS.start(),
+                PARTS.end()
+            ),
+            CliError::EmptyRange(range) => write!(f, "day range '{}' is empty", range),
+            CliError::ConflictingOptions(a, b) => {
+                write!(f, "{} cannot be combined with {}", a, b)
+            }
+            CliError::NeedsSingleDay(opt) => write!(f, "{} needs exactly one --day", opt),
+            CliError::MissingOption(opt) => write!(f, "{} is required", opt),
+            CliError::NotAPuzzleDay(day) => write!(
+                f,
+                "there is no day {} (puzzles run {}..{})",
+                day,
+                PUZZLE_DAYS.start(),
+                PUZZLE_DAYS.end()
+            ),
+            CliError::UnknownFormat(format) => write!(
+                f,
+                "unknown format '{}' (available: text, json, csv)",
+                format
+            ),
+        }
+    }
+}
+
+/// Parse the arguments following the program name.
+pub fn parse_args<I>(args: I) -> Result<Command, CliError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter().peekable();
+
+    let kind = match args.peek().map(|s| s.as_str()) {
+        Some("help") => return Ok(Command::Help),
+        Some(arg) if !arg.starts_with('-') => {
+            let kind = match arg {
+                "run" => Kind::Run,
+                "bench" => Kind::Bench,
+                "verify" => Kind::Verify,
+                "fetch" => Kind::Fetch,
+                "examples" => Kind::Examples,
+                "analyze" => Kind::Analyze,
+                _ => return Err(CliError::UnknownCommand(arg.to_string())),
+            };
+            args.next();
+            kind
+        }
+        _ => Kind::Run,
+    };
+
+    let mut days: Option<Vec<u32>> = None;
+    let mut all = false;
+    let mut parts: Option<Vec<u32>> = None;
+    let mut input_dir: Option<PathBuf> = None;
+    let mut input: Option<Source> = None;
+    let mut time = false;
+    let mut format = Format::Text;
+    let mut iterations = DEFAULT_ITERATIONS;
+    let mut warmup = DEFAULT_WARMUP;
+    let mut answers: Option<PathBuf> = None;
+    let mut record = false;
+    let mut base_url: Option<String> = None;
+    let mut page: Option<PathBuf> = None;
+    let mut fixtures_dir: Option<PathBuf> = None;
+    let mut analyze_options = AnalyzeOptions::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" | "-h" => return Ok(Command::Help),
+            "--all" if kind != Kind::Analyze => all = true,
+            "--day" if kind != Kind::Analyze => {
+                let value = args.next().ok_or(CliError::MissingValue(arg))?;
+                days = Some(parse_days(&value, !kind.solves())?);
+            }
+            "--part" if kind.solves() => {
+                let value = args.next().ok_or(CliError::MissingValue(arg.clone()))?;
+                let part = parse_number(&arg, &value)?;
+                if !PARTS.contains(&part) {
+                    return Err(CliError::UnknownPart(part));
+                }
+                parts = Some(vec![part]);
+            }
+            "--input-dir" if kind != Kind::Examples => {
+                let value = args.next().ok_or(CliError::MissingValue(arg))?;
+                input_dir = Some(PathBuf::from(value));
+            }
+            "--input" if kind.solves() || kind == Kind::Analyze => {
+                let value = args.next().ok_or(CliError::MissingValue(arg))?;
+                input = Some(Source::from_arg(&value));
+            }
+            "--time" if kind == Kind::Run => time = true,
+            "--format" if kind == Kind::Run => {
+                let value = args.next().ok_or(CliError::MissingValue(arg))?;
+                format = match value.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    "csv" => Format::Csv,
+                    _ => return Err(CliError::UnknownFormat(value)),
+                };
+            }
+            "--iterations" if kind == Kind::Bench => {
+                let value = args.next().ok_or(CliError::MissingValue(arg.clone()))?;
+                iterations = parse_positive(&arg, &value)?;
+            }
+            "--warmup" if kind == Kind::Bench => {
+                let value = args.next().ok_or(CliError::MissingValue(arg.clone()))?;
+                warmup = parse_number(&arg, &value)?;
+            }
+            "--answers" if kind == Kind::Verify => {
+                let value = args.next().ok_or(CliError::MissingValue(arg))?;
+                answers = Some(PathBuf::from(value));
+            }
+            "--record" if kind == Kind::Verify => record = true,
+            "--base-url" if kind == Kind::Fetch => {
+                base_url = Some(args.next().ok_or(CliError::MissingValue(arg))?);
+            }
+            "--page" if kind == Kind::Examples => {
+                let value = args.next().ok_or(CliError::MissingValue(arg))?;
+                page = Some(PathBuf::from(value));
+            }
+            "--fixtures" if kind == Kind::Examples => {
+                let value = args.next().ok_or(CliError::MissingValue(arg))?;
+                fixtures_dir = Some(PathBuf::from(value));
+            }
+            "--window" if kind == Kind::Analyze => {
+                let value = args.next().ok_or(CliError::MissingValue(arg.clone()))?;
+                analyze_options.window = usize::try_from(parse_positive(&arg, &value)?).unwrap();
+            }
+            "--threshold" if kind == Kind::Analyze => {
+                let value = args.next().ok_or(CliError::MissingValue(arg.clone()))?;
+                analyze_options.threshold = value
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|z| z.is_finite())
+                    .ok_or(CliError::BadNumber(arg, value))?;
+            }
+            _ => return Err(CliError::UnknownOption(arg)),
+        }
+    }
+
+    if all && days.is_some() {
+        return Err(CliError::ConflictingOptions(
+            "--all".to_string(),
+            "--day".to_string(),
+        ));
+    }
+
+    if kind == Kind::Examples {
+        let day = match days.as_deref() {
+            Some([day]) => *day,
+            Some(_) => return Err(CliError::NeedsSingleDay("examples".to_string())),
+            None => return Err(CliError::MissingOption("--day".to_string())),
+        };
+        return Ok(Command::Examples(ExamplesArgs {
+            day,
+            page: page.ok_or(CliError::MissingOption("--page".to_string()))?,
+            fixtures_dir: fixtures_dir.unwrap_or_else(examples::default_fixtures_dir),
+        }));
+    }
+
+    let input_dir = input_dir.unwrap_or_else(input::default_dir);
+    if kind == Kind::Analyze {
+        return Ok(Command::Analyze(AnalyzeArgs {
+            input: input.unwrap_or_else(|| Source::File(input::path_for_day(&input_dir, 1))),
+            options: analyze_options,
+        }));
+    }
+    if kind == Kind::Fetch {
+        return Ok(Command::Fetch(FetchArgs {
+            days: days.unwrap_or_else(|| Selection::all().days),
+            input_dir,
+            base_url: base_url.unwrap_or_else(fetch::default_base_url),
+        }));
+    }
+
+    let mut selection = Selection::all();
+    if let Some(days) = days {
+        selection.days = days;
+    }
+    if let Some(parts) = parts {
+        selection.parts = parts;
+    }
+    if input.is_some() && selection.days.len() != 1 {
+        return Err(CliError::NeedsSingleDay("--input".to_string()));
+    }
+    let run = RunArgs {
+        selection,
+        input_dir,
+        input,
+        time,
+        format,
+    };
+    Ok(match kind {
+        Kind::Run => Command::Run(run),
+        Kind::Bench => Command::Bench(BenchArgs {
+            run,
+            iterations,
+            warmup,
+        }),
+        Kind::Verify => Command::Verify(VerifyArgs {
+            run,
+            answers: answers.unwrap_or_else(answers::default_path),
+            record,
+        }),
+        Kind::Fetch | Kind::Examples | Kind::Analyze => {
+            unreachable!("returned before building a selection")
+        }
+    })
+}
+
+fn parse_number(opt: &str, value: &str) -> Result<u32, CliError> {
+    value
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| CliError::BadNumber(opt.to_string(), value.to_string()))
+}
+
+/// Counts of runs or depths, where zero would leave nothing to measure
+fn parse_positive(opt: &str, value: &str) -> Result<u32, CliError> {
+    match parse_number(opt, value)? {
+        0 => Err(CliError::Zero(opt.to_string())),
+        n => Ok(n),
+    }
+}
+
+/// Accepts "5" or an inclusive range "3..7". Days must have a registered
+/// solution, unless `any_puzzle_day` allows every day of the calendar.
+fn parse_days(value: &str, any_puzzle_day: bool) -> Result<Vec<u32>, CliError> {
+    let days = match value.split_once("..") {
+        Some((first, last)) => {
+            let first = parse_number("--day", first)?;
+            let last = parse_number("--day", last)?;
+            if first > last {
+                return Err(CliError::EmptyRange(value.to_string()));
+            }
+            (first..=last).collect::<Vec<u32>>()
+        }
+        None => vec![parse_number("--day", value)?],
+    };
+
+    if any_puzzle_day {
+        match days.iter().find(|day| !PUZZLE_DAYS.contains(day)) {
+            Some(day) => Err(CliError::NotAPuzzleDay(*day)),
+            None => Ok(days),
+        }
+    } else {
+        match days.iter().find(|day| solution::for_day(**day).is_none()) {
+            Some(day) => Err(CliError::UnknownDay(*day)),
+            None => Ok(days),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, CliError> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    fn run(days: Vec<u32>, parts: Vec<u32>) -> Result<Command, CliError> {
+        Ok(Command::Run(RunArgs {
+            selection: Selection { days, parts },
+            input_dir: input::default_dir(),
+            input: None,
+            time: false,
+            format: Format::Text,
+        }))
+    }
+
+    fn all() -> Result<Command, CliError> {
+        let Selection { days, parts } = Selection::all();
+        run(days, parts)
+    }
+
+    #[test]
+    fn runs_everything_by_default() {
+        assert_eq!(all(), parse(&[]));
+        assert_eq!(all(), parse(&["run"]));
+        assert_eq!(all(), parse(&["run", "--all"]));
+    }
+
+    #[test]
+    fn selects_day_and_part() {
+        assert_eq!(
+            run(vec![5], vec![2]),
+            parse(&["run", "--day", "5", "--part", "2"])
+        );
+        assert_eq!(run(vec![5], vec![1, 2]), parse(&["--day", "5"]));
+    }
+
+    #[test]
+    fn selects_range_of_days() {
+        assert_eq!(
+            run(vec![3, 4, 5, 6, 7], vec![1, 2]),
+            parse(&["run", "--day", "3..7"])
+        );
+    }
+
+    #[test]
+    fn reads_inputs_from_chosen_location() {
+        let parsed = parse(&["--input-dir", "/tmp/inputs"]);
+        let Ok(Command::Run(args)) = parsed else {
+            panic!("expected run, found {:?}", parsed)
+        };
+        assert_eq!(PathBuf::from("/tmp/inputs"), args.input_dir);
+
+        let parsed = parse(&["--day", "5", "--input", "-"]);
+        let Ok(Command::Run(args)) = parsed else {
+            panic!("expected run, found {:?}", parsed)
+        };
+        assert_eq!(Some(Source::Stdin), args.input);
+    }
+
+    #[test]
+    fn parses_timing_options() {
+        let parsed = parse(&["run", "--day", "7", "--time"]);
+        let Ok(Command::Run(args)) = parsed else {
+            panic!("expected run, found {:?}", parsed)
+        };
+        assert!(args.time);
+
+        let parsed = parse(&["bench", "--day", "7", "--iterations", "50", "--warmup", "0"]);
+        let Ok(Command::Bench(args)) = parsed else {
+            panic!("expected bench, found {:?}", parsed)
+        };
+        assert_eq!(
+            (vec![7], 50, 0),
+            (args.run.selection.days, args.iterations, args.warmup)
+        );
+
+        let parsed = parse(&["bench"]);
+        let Ok(Command::Bench(args)) = parsed else {
+            panic!("expected bench, found {:?}", parsed)
+        };
+        assert_eq!(
+            (DEFAULT_ITERATIONS, DEFAULT_WARMUP),
+            (args.iterations, args.warmup)
+        );
+
+        assert_eq!(
+            Err(CliError::Zero("--iterations".to_string())),
+            parse(&["bench", "--iterations", "0"])
+        );
+        assert_eq!(
+            Err(CliError::Zero("--window".to_string())),
+            parse(&["analyze", "--window", "0"])
+        );
+    }
+
+    #[test]
+    fn parses_verify_options() {
+        let parsed = parse(&["verify", "--day", "1..2"]);
+        let Ok(Command::Verify(args)) = parsed else {
+            panic!("expected verify, found {:?}", parsed)
+        };
+        assert_eq!(answers::default_path(), args.answers);
+        assert!(!args.record);
+
+        let parsed = parse(&["verify", "--answers", "mine.toml", "--record"]);
+        let Ok(Command::Verify(args)) = parsed else {
+            panic!("expected verify, found {:?}", parsed)
+        };
+        assert_eq!(PathBuf::from("mine.toml"), args.answers);
+        assert!(args.record);
+    }
+
+    #[test]
+    fn parses_fetch_options() {
+        let parsed = parse(&["fetch", "--day", "9", "--base-url", "http://localhost:8080"]);
+        let Ok(Command::Fetch(args)) = parsed else {
+            panic!("expected fetch, found {:?}", parsed)
+        };
+        assert_eq!(vec![9], args.days);
+        assert_eq!("http://localhost:8080", args.base_url);
+
+        assert_eq!(
+            Err(CliError::NotAPuzzleDay(26)),
+            parse(&["fetch", "--day", "24..26"])
+        );
+        assert_eq!(
+            Err(CliError::UnknownOption("--part".to_string())),
+            parse(&["fetch", "--part", "1"])
+        );
+    }
+
+    #[test]
+    fn parses_output_format() {
+        let parsed = parse(&["run", "--day", "2", "--format", "json"]);
+        let Ok(Command::Run(args)) = parsed else {
+            panic!("expected run, found {:?}", parsed)
+        };
+        assert_eq!(Format::Json, args.format);
+
+        assert_eq!(
+            Err(CliError::UnknownFormat("xml".to_string())),
+            parse(&["--format", "xml"])
+        );
+        assert_eq!(
+            Err(CliError::UnknownOption("--format".to_string())),
+            parse(&["bench", "--format", "csv"])
+        );
+    }
+
+    #[test]
+    fn parses_analyze_options() {
+        let parsed = parse(&[
+            "analyze",
+            "--input",
+            "-",
+            "--window",
+            "5",
+            "--threshold",
+            "2.5",
+        ]);
+        assert_eq!(
+            Ok(Command::Analyze(AnalyzeArgs {
+                input: Source::Stdin,
+                options: AnalyzeOptions {
+                    window: 5,
+                    threshold: 2.5
+                },
+            })),
+            parsed
+        );
+
+        let parsed = parse(&["analyze"]);
+        let Ok(Command::Analyze(args)) = parsed else {
+            panic!("expected analyze, found {:?}", parsed)
+        };
+        assert_eq!(
+            Source::File(input::path_for_day(&input::default_dir(), 1)),
+            args.input
+        );
+        assert_eq!(
+            Err(CliError::BadNumber(
+                "--threshold".to_string(),
+                "lots".to_string()
+            )),
+            parse(&["analyze", "--threshold", "lots"])
+        );
+        assert_eq!(
+            Err(CliError::UnknownOption("--day".to_string())),
+            parse(&["analyze", "--day", "2"])
+        );
+    }
+
+    #[test]
+    fn parses_examples_options() {
+        let parsed = parse(&["examples", "--day", "9", "--page", "day9.html"]);
+        let Ok(Command::Examples(args)) = parsed else {
+            panic!("expected examples, found {:?}", parsed)
+        };
+        assert_eq!(9, args.day);
+        assert_eq!(PathBuf::from("day9.html"), args.page);
+        assert_eq!(examples::default_fixtures_dir(), args.fixtures_dir);
+
+        assert_eq!(
+            Err(CliError::MissingOption("--page".to_string())),
+            parse(&["examples", "--day", "9"])
+        );
+        assert_eq!(
+            Err(CliError::NeedsSingleDay("examples".to_string())),
+            parse(&["examples", "--day", "1..2", "--page", "p.html"])
+        );
+    }
+
+    #[test]
+    fn keeps_options_to_their_commands() {
+        assert_eq!(
+            Err(CliError::UnknownOption("--iterations".to_string())),
+            parse(&["run", "--iterations", "5"])
+        );
+        assert_eq!(
+            Err(CliError::UnknownOption("--time".to_string())),
+            parse(&["bench", "--time"])
+        );
+        assert_eq!(
+            Err(CliError::UnknownOption("--record".to_string())),
+            parse(&["run", "--record"])
+        );
+    }
+
+    #[test]
+    fn rejects_input_for_many_days() {
+        assert_eq!(
+            Err(CliError::NeedsSingleDay("--input".to_string())),
+            parse(&["--input", "day5.txt"])
+        );
+        assert_eq!(
+            Err(CliError::NeedsSingleDay("--input".to_string())),
+            parse(&["--day", "3..4", "--input", "-"])
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_days_and_parts() {
+        assert_eq!(
+            Err(CliError::UnknownDay(12)),
+            parse(&["run", "--day", "12"])
+        );
+        assert_eq!(
+            Err(CliError::UnknownDay(9)),
+            parse(&["run", "--day", "6..10"])
+        );
+        assert_eq!(
+            Err(CliError::UnknownPart(3)),
+            parse(&["run", "--part", "3"])
+        );
+        assert_eq!(
+            Err(CliError::EmptyRange("7..3".to_string())),
+            parse(&["run", "--day", "7..3"])
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(
+            Err(CliError::BadNumber("--day".to_string(), "five".to_string())),
+            parse(&["run", "--day", "five"])
+        );
+        assert_eq!(
+            Err(CliError::MissingValue("--part".to_string())),
+            parse(&["run", "--part"])
+        );
+        assert_eq!(
+            Err(CliError::UnknownCommand("walk".to_string())),
+            parse(&["walk"])
+        );
+        assert_eq!(
+            Err(CliError::UnknownOption("--days".to_string())),
+            parse(&["--days", "1"])
+        );
+        assert_eq!(
+            Err(CliError::ConflictingOptions(
+                "--all".to_string(),
+                "--day".to_string()
+            )),
+            parse(&["--all", "--day", "1"])
+        );
+    }
+}
@@ -1,46 +1,702 @@
-use itertools::izip;
-use std::str::FromStr;
+use crate::parse::{self, ParseResult};
+use crate::solution::{Solution, SolveResult};
 
-pub fn input() -> Vec<i32> {
-    include_str!("day1_input.txt")
-        .split_whitespace()
-        .map(|s| i32::from_str(s).unwrap())
-        .collect::<Vec<i32>>()
+const DAY: u32 = 1;
+
+/// Count values larger than the one before, consuming `depths` lazily
+pub fn count_increases<T: PartialOrd>(depths: impl IntoIterator<Item = T>) -> usize {
+    let mut depths = depths.into_iter();
+    let Some(mut previous) = depths.next() else {
+        return 0;
+    };
+    let mut count = 0;
+    for depth in depths {
+        if depth > previous {
+            count += 1;
+        }
+        previous = depth;
+    }
+    count
 }
 
-pub fn count_increases(report: &[i32]) -> i32 {
+/// The sum of each three depths in a row; empty for fewer than three
+pub fn sum_triples(report: &[i32]) -> Vec<i32> {
     report
-        .iter()
-        .zip(report[1..].iter())
-        .fold(0, |acc, (l, r)| if r > l { acc + 1 } else { acc })
+        .windows(3)
+        .map(|triple| triple.iter().sum())
+        .collect()
 }
 
-pub fn sum_triples(report: &[i32]) -> Vec<i32> {
-    assert!(
-        report.len() >= 3,
-        "require at least three elements, found {}",
-        report.len()
-    );
-    izip!(report, report[1..].iter(), report[2..].iter())
-        .map(|(a, b, c)| a + b + c)
-        .collect::<Vec<i32>>()
+/// Consecutive depths starting at index `start` of the report
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Run {
+    pub start: usize,
+    pub length: usize,
+}
+
+/// A fall in depth from `from` to `to`, the depth at `index`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Decrease {
+    pub index: usize,
+    pub from: i32,
+    pub to: i32,
+}
+
+impl Decrease {
+    pub fn size(&self) -> i64 {
+        i64::from(self.from) - i64::from(self.to)
+    }
+}
+
+/// A depth far from the mean of the `window` depths before it
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Outlier {
+    pub index: usize,
+    pub depth: i32,
+    pub z_score: f64,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AnalyzeOptions {
+    /// How many preceding depths each z-score is measured against
+    pub window: usize,
+    /// Depths whose z-score is larger than this, either way, are outliers
+    pub threshold: f64,
+}
+
+impl Default for AnalyzeOptions {
+    fn default() -> Self {
+        AnalyzeOptions {
+            window: 10,
+            threshold: 3.0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Analysis {
+    pub depths: usize,
+    pub increases: usize,
+    /// Increases in the sums of three, as for part 2
+    pub window_increases: usize,
+    /// The first of the longest strictly increasing runs, if any depth rises
+    pub longest_rise: Option<Run>,
+    /// Every run of two or more equal depths
+    pub plateaus: Vec<Run>,
+    pub largest_drop: Option<Decrease>,
+    pub outliers: Vec<Outlier>,
+}
+
+pub fn analyze(report: &[i32], options: &AnalyzeOptions) -> Analysis {
+    let mut longest_rise: Option<Run> = None;
+    let mut plateaus = Vec::new();
+    let mut largest_drop: Option<Decrease> = None;
+    let mut rise = Run {
+        start: 0,
+        length: 1,
+    };
+    let mut plateau = rise;
+
+    for (index, pair) in report.windows(2).enumerate().map(|(i, pair)| (i + 1, pair)) {
+        let (previous, depth) = (pair[0], pair[1]);
+
+        if depth > previous {
+            rise.length += 1;
+            if longest_rise.map_or(true, |longest| rise.length > longest.length) {
+                longest_rise = Some(rise);
+            }
+        } else {
+            rise = Run {
+                start: index,
+                length: 1,
+            };
+        }
+
+        if depth == previous {
+            plateau.length += 1;
+        } else {
+            if plateau.length > 1 {
+                plateaus.push(plateau);
+            }
+            plateau = Run {
+                start: index,
+                length: 1,
+            };
+        }
+
+        let drop = Decrease {
+            index,
+            from: previous,
+            to: depth,
+        };
+        if drop.size() > largest_drop.map_or(0, |largest| largest.size()) {
+            largest_drop = Some(drop);
+        }
+    }
+    if plateau.length > 1 {
+        plateaus.push(plateau);
+    }
+
+    Analysis {
+        depths: report.len(),
+        increases: count_increases(report),
+        window_increases: count_increases(window::windows(
+            report.iter().copied(),
+            3,
+            window::Sum::default(),
+        )),
+        longest_rise,
+        plateaus,
+        largest_drop,
+        outliers: outliers(report, options),
+    }
+}
+
+/// Rolling z-scores, skipping windows whose depths are all the same
+fn outliers(report: &[i32], options: &AnalyzeOptions) -> Vec<Outlier> {
+    assert!(options.window > 0, "window must hold at least one depth");
+    let n = i128::try_from(options.window).unwrap();
+    // exact running sums, so long reports don't accumulate rounding error
+    let mut sum: i128 = 0;
+    let mut squares: i128 = 0;
+    let mut result = Vec::new();
+
+    for (index, depth) in report.iter().copied().enumerate() {
+        if index >= options.window {
+            let spread = n * squares - sum * sum;
+            if spread > 0 {
+                let mean = sum as f64 / n as f64;
+                let deviation = (spread as f64).sqrt() / n as f64;
+                let z_score = (f64::from(depth) - mean) / deviation;
+                if z_score.abs() > options.threshold {
+                    result.push(Outlier {
+                        index,
+                        depth,
+                        z_score,
+                    });
+                }
+            }
+            let leaving = i128::from(report[index - options.window]);
+            sum -= leaving;
+            squares -= leaving * leaving;
+        }
+        sum += i128::from(depth);
+        squares += i128::from(depth) * i128::from(depth);
+    }
+    result
+}
+
+/// Sliding windows over a stream of depths, summarised as they slide
+///
+/// `windows(depths, 3, Sum::default())` yields the sum of each run of three
+/// consecutive depths, holding only the current window in memory.
+pub mod window {
+    use std::collections::VecDeque;
+
+    /// A summary of the values currently in a window, updated as values
+    /// enter and leave it rather than recomputed for each window
+    pub trait Aggregate {
+        type Output;
+
+        fn push(&mut self, value: i32);
+        /// Remove `value`, the oldest value in the window
+        fn evict(&mut self, value: i32);
+        fn value(&self) -> Self::Output;
+    }
+
+    pub struct Windows<I, A> {
+        values: I,
+        width: usize,
+        window: VecDeque<i32>,
+        aggregate: A,
+    }
+
+    /// Summarise each window of `width` consecutive values with `aggregate`
+    pub fn windows<I, A>(values: I, width: usize, aggregate: A) -> Windows<I::IntoIter, A>
+    where
+        I: IntoIterator<Item = i32>,
+        A: Aggregate,
+    {
+        assert!(width > 0, "window width must be at least 1");
+        Windows {
+            values: values.into_iter(),
+            width,
+            window: VecDeque::with_capacity(width),
+            aggregate,
+        }
+    }
+
+    impl<I: Iterator<Item = i32>, A: Aggregate> Iterator for Windows<I, A> {
+        type Item = A::Output;
+
+        fn next(&mut self) -> Option<A::Output> {
+            if self.window.len() == self.width {
+                let oldest = self.window.pop_front().unwrap();
+                self.aggregate.evict(oldest);
+            }
+            while self.window.len() < self.width {
+                let value = self.values.next()?;
+                self.window.push_back(value);
+                self.aggregate.push(value);
+            }
+            Some(self.aggregate.value())
+        }
+    }
+
+    #[derive(Debug, Default)]
+    pub struct Sum {
+        total: i64,
+    }
+
+    impl Aggregate for Sum {
+        type Output = i64;
+
+        fn push(&mut self, value: i32) {
+            self.total += i64::from(value);
+        }
+
+        fn evict(&mut self, value: i32) {
+            self.total -= i64::from(value);
+        }
+
+        fn value(&self) -> i64 {
+            self.total
+        }
+    }
+
+    #[derive(Debug, Default)]
+    pub struct Mean {
+        sum: Sum,
+        count: usize,
+    }
+
+    impl Aggregate for Mean {
+        type Output = f64;
+
+        fn push(&mut self, value: i32) {
+            self.sum.push(value);
+            self.count += 1;
+        }
+
+        fn evict(&mut self, value: i32) {
+            self.sum.evict(value);
+            self.count -= 1;
+        }
+
+        fn value(&self) -> f64 {
+            self.sum.value() as f64 / self.count as f64
+        }
+    }
+
+    /// Monotonic deque: candidates for the minimum in window order, each
+    /// no smaller than the one before, so the front is the minimum
+    #[derive(Debug, Default)]
+    pub struct Min {
+        candidates: VecDeque<i32>,
+    }
+
+    impl Aggregate for Min {
+        type Output = i32;
+
+        fn push(&mut self, value: i32) {
+            // anything larger can never be the minimum while `value` is in
+            // the window, and `value` will outlast it
+            while self.candidates.back().is_some_and(|back| *back > value) {
+                self.candidates.pop_back();
+            }
+            self.candidates.push_back(value);
+        }
+
+        fn evict(&mut self, value: i32) {
+            if self.candidates.front() == Some(&value) {
+                self.candidates.pop_front();
+            }
+        }
+
+        fn value(&self) -> i32 {
+            *self.candidates.front().expect("window is empty")
+        }
+    }
+
+    /// Monotonic deque, as for `Min` but non-increasing
+    #[derive(Debug, Default)]
+    pub struct Max {
+        candidates: VecDeque<i32>,
+    }
+
+    impl Aggregate for Max {
+        type Output = i32;
+
+        fn push(&mut self, value: i32) {
+            while self.candidates.back().is_some_and(|back| *back < value) {
+                self.candidates.pop_back();
+            }
+            self.candidates.push_back(value);
+        }
+
+        fn evict(&mut self, value: i32) {
+            if self.candidates.front() == Some(&value) {
+                self.candidates.pop_front();
+            }
+        }
+
+        fn value(&self) -> i32 {
+            *self.candidates.front().expect("window is empty")
+        }
+    }
+
+    /// Keeps the window sorted; an even width gives the mean of the middle pair
+    #[derive(Debug, Default)]
+    pub struct Median {
+        sorted: Vec<i32>,
+    }
+
+    impl Aggregate for Median {
+        type Output = f64;
+
+        fn push(&mut self, value: i32) {
+            let at = self.sorted.partition_point(|v| *v < value);
+            self.sorted.insert(at, value);
+        }
+
+        fn evict(&mut self, value: i32) {
+            let at = self.sorted.partition_point(|v| *v < value);
+            self.sorted.remove(at);
+        }
+
+        fn value(&self) -> f64 {
+            let mid = self.sorted.len() / 2;
+            if self.sorted.len() % 2 == 0 {
+                (f64::from(self.sorted[mid - 1]) + f64::from(self.sorted[mid])) / 2.0
+            } else {
+                f64::from(self.sorted[mid])
+            }
+        }
+    }
+}
+
+/// Sonar sweeps read straight from a reader, one line at a time
+///
+/// Only the current line and the last `width` depths are held in memory, so
+/// reports of any size can be swept in a single pass.
+pub mod stream {
+    use super::DAY;
+    use crate::parse::{Line, ParseError, ParseResult};
+    use std::collections::VecDeque;
+    use std::error::Error;
+    use std::fmt;
+    use std::io::{self, BufRead};
+    use std::vec;
+
+    #[derive(Debug)]
+    pub enum ReadError {
+        Io { line: usize, err: io::Error },
+        Parse(ParseError),
+    }
+
+    impl fmt::Display for ReadError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                ReadError::Io { line, err } => write!(f, "line {}: {}", line, err),
+                ReadError::Parse(err) => write!(f, "{}", err),
+            }
+        }
+    }
+
+    impl Error for ReadError {}
+
+    /// The depths in a report, with any malformed line reported by number
+    pub struct Depths<R> {
+        lines: io::Lines<R>,
+        number: usize,
+        pending: vec::IntoIter<i32>,
+    }
+
+    pub fn depths<R: BufRead>(reader: R) -> Depths<R> {
+        Depths {
+            lines: reader.lines(),
+            number: 0,
+            pending: Vec::new().into_iter(),
+        }
+    }
+
+    impl<R: BufRead> Iterator for Depths<R> {
+        type Item = Result<i32, ReadError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if let Some(depth) = self.pending.next() {
+                    return Some(Ok(depth));
+                }
+                self.number += 1;
+                let text = match self.lines.next()? {
+                    Ok(text) => text,
+                    Err(err) => {
+                        let line = self.number;
+                        return Some(Err(ReadError::Io { line, err }));
+                    }
+                };
+                let line = Line::new(DAY, self.number, &text);
+                let parsed = line
+                    .text
+                    .split_whitespace()
+                    .map(|token| line.parse(token))
+                    .collect::<ParseResult<Vec<i32>>>();
+                match parsed {
+                    Ok(depths) => self.pending = depths.into_iter(),
+                    Err(err) => return Some(Err(ReadError::Parse(err))),
+                }
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq, Default)]
+    pub struct Sweep {
+        pub depths: usize,
+        pub increases: usize,
+        /// Increases in the sum of each `width` consecutive depths
+        pub window_increases: usize,
+    }
+
+    /// Count increases and windowed increases in one pass over `reader`
+    pub fn sweep<R: BufRead>(reader: R, width: usize) -> Result<Sweep, ReadError> {
+        assert!(width > 0, "window width must be at least 1");
+        let mut result = Sweep::default();
+        let mut recent: VecDeque<i32> = VecDeque::with_capacity(width + 1);
+
+        for depth in depths(reader) {
+            let depth = depth?;
+            result.depths += 1;
+            if recent.back().is_some_and(|previous| depth > *previous) {
+                result.increases += 1;
+            }
+            recent.push_back(depth);
+            // neighbouring windows share all but their ends, so the sum
+            // goes up exactly when the entering depth beats the leaving one
+            if recent.len() > width && depth > recent.pop_front().unwrap() {
+                result.window_increases += 1;
+            }
+        }
+        Ok(result)
+    }
+}
+
+pub struct Day1 {
+    report: Vec<i32>,
+}
+
+/// The depths in a report, any number to a line
+pub fn parse_report(input: &[String]) -> ParseResult<Vec<i32>> {
+    parse::lines(DAY, input)
+        .flat_map(|line| line.text.split_whitespace().map(move |s| line.parse(s)))
+        .collect()
+}
+
+impl Solution for Day1 {
+    fn parse(input: &[String]) -> ParseResult<Self> {
+        let report = parse_report(input)?;
+        Ok(Day1 { report })
+    }
+
+    fn part1(&self) -> SolveResult {
+        count_increases(&self.report).try_into()
+    }
+
+    fn part2(&self) -> Option<SolveResult> {
+        let sums = window::windows(self.report.iter().copied(), 3, window::Sum::default());
+        Some(count_increases(sums).try_into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::count_increases;
-    use super::sum_triples;
+    use super::stream::{self, ReadError, Sweep};
+    use super::window::{windows, Max, Mean, Median, Min, Sum};
+    use super::{
+        analyze, count_increases, parse_report, sum_triples, AnalyzeOptions, Decrease, Run, DAY,
+    };
+    use crate::examples;
 
-    const TEST_REPORT: &[i32] = &[199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+    fn report() -> Vec<i32> {
+        let (input, _) = examples::load(&examples::default_fixtures_dir(), DAY).unwrap();
+        parse_report(&input).unwrap()
+    }
 
     #[test]
     fn counts_number_of_increases() {
-        assert_eq!(count_increases(TEST_REPORT), 7);
+        assert_eq!(count_increases(report()), 7);
     }
 
     #[test]
     fn creates_three_item_window() {
         let expected = [607, 618, 618, 617, 647, 716, 769, 792];
-        assert_eq!(sum_triples(TEST_REPORT).as_slice(), expected);
+        assert_eq!(sum_triples(&report()).as_slice(), expected);
+        assert_eq!(Vec::<i32>::new(), sum_triples(&[199, 200]));
+    }
+
+    #[test]
+    fn sums_windows_of_any_width() {
+        let sums: Vec<i64> = windows(report(), 4, Sum::default()).collect();
+        assert_eq!(vec![817, 818, 825, 857, 916, 976, 1032], sums);
+        assert_eq!(0, windows([1, 2], 3, Sum::default()).count());
+    }
+
+    #[test]
+    fn counts_increases_lazily_over_windows() {
+        let sums = windows(report(), 3, Sum::default());
+        assert_eq!(5, count_increases(sums));
+        assert_eq!(0, count_increases(Vec::<i32>::new()));
+    }
+
+    #[test]
+    fn tracks_window_minimum_and_maximum() {
+        let values = [5, 1, 3, 3, 2, 7, 7, 0, 4];
+        let mins: Vec<i32> = windows(values, 3, Min::default()).collect();
+        let maxes: Vec<i32> = windows(values, 3, Max::default()).collect();
+        assert_eq!(vec![1, 1, 2, 2, 2, 0, 0], mins);
+        assert_eq!(vec![5, 3, 3, 7, 7, 7, 7], maxes);
+    }
+
+    #[test]
+    fn matches_brute_force_window_aggregates() {
+        let values: Vec<i32> = (0..200).map(|i| (i * 7919 % 101) - 50).collect();
+        for width in [1, 2, 5, 8] {
+            let expected = values.windows(width).map(|w| {
+                let mut sorted = w.to_vec();
+                sorted.sort();
+                let mid = width / 2;
+                let median = if width % 2 == 0 {
+                    f64::from(sorted[mid - 1] + sorted[mid]) / 2.0
+                } else {
+                    f64::from(sorted[mid])
+                };
+                (sorted[0], sorted[width - 1], median)
+            });
+            let actual = windows(values.iter().copied(), width, Min::default())
+                .zip(windows(values.iter().copied(), width, Max::default()))
+                .zip(windows(values.iter().copied(), width, Median::default()))
+                .map(|((min, max), median)| (min, max, median));
+            assert!(expected.eq(actual), "width {}", width);
+        }
+    }
+
+    #[test]
+    fn averages_windows() {
+        let means: Vec<f64> = windows([1, 2, 3, 4], 2, Mean::default()).collect();
+        assert_eq!(vec![1.5, 2.5, 3.5], means);
+    }
+
+    #[test]
+    fn sweeps_a_reader_in_one_pass() {
+        let report = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n";
+        assert_eq!(
+            Sweep {
+                depths: 10,
+                increases: 7,
+                window_increases: 5
+            },
+            stream::sweep(report.as_bytes(), 3).unwrap()
+        );
+        let sweep = stream::sweep(report.as_bytes(), 1).unwrap();
+        assert_eq!(sweep.increases, sweep.window_increases);
+    }
+
+    #[test]
+    fn sweep_matches_collected_counts() {
+        let report = (0..100_000)
+            .map(|i| ((i * 37) % 1000).to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        let depths: Vec<i32> = report.lines().map(|l| l.parse().unwrap()).collect();
+
+        let sweep = stream::sweep(report.as_bytes(), 5).unwrap();
+        assert_eq!(count_increases(&depths), sweep.increases);
+        assert_eq!(
+            count_increases(windows(depths.iter().copied(), 5, Sum::default())),
+            sweep.window_increases
+        );
+    }
+
+    #[test]
+    fn reports_malformed_line_number() {
+        let err = stream::sweep("199\n200\n\n2O8\n".as_bytes(), 3).unwrap_err();
+        let ReadError::Parse(err) = err else {
+            panic!("expected a parse error, found {:?}", err)
+        };
+        assert_eq!((4, 1), (err.line, err.column));
+        assert_eq!("2O8", err.text);
+    }
+
+    #[test]
+    fn analyzes_example_report() {
+        let analysis = analyze(&report(), &AnalyzeOptions::default());
+        assert_eq!(
+            (10, 7, 5),
+            (
+                analysis.depths,
+                analysis.increases,
+                analysis.window_increases
+            )
+        );
+        assert_eq!(
+            Some(Run {
+                start: 0,
+                length: 4
+            }),
+            analysis.longest_rise
+        );
+        assert_eq!(
+            Some(Decrease {
+                index: 4,
+                from: 210,
+                to: 200
+            }),
+            analysis.largest_drop
+        );
+        assert!(analysis.plateaus.is_empty());
+        assert!(analysis.outliers.is_empty());
+    }
+
+    #[test]
+    fn finds_plateaus() {
+        let analysis = analyze(&[3, 3, 4, 5, 5, 5, 2, 2], &AnalyzeOptions::default());
+        let plateaus = vec![
+            Run {
+                start: 0,
+                length: 2,
+            },
+            Run {
+                start: 3,
+                length: 3,
+            },
+            Run {
+                start: 6,
+                length: 2,
+            },
+        ];
+        assert_eq!(plateaus, analysis.plateaus);
+        assert_eq!(3, analysis.longest_rise.unwrap().length);
+    }
+
+    #[test]
+    fn flags_rolling_z_score_outliers() {
+        let report = [100, 102, 98, 101, 99, 100, 160, 101, 99, 100];
+        let options = AnalyzeOptions {
+            window: 5,
+            threshold: 3.0,
+        };
+        let outliers = analyze(&report, &options).outliers;
+
+        assert_eq!(1, outliers.len());
+        assert_eq!((6, 160), (outliers[0].index, outliers[0].depth));
+        assert!((outliers[0].z_score - 42.43).abs() < 0.01);
+    }
+
+    #[test]
+    fn analyzes_short_and_flat_reports() {
+        let analysis = analyze(&[7, 7, 7], &AnalyzeOptions::default());
+        assert_eq!(None, analysis.longest_rise);
+        assert_eq!(None, analysis.largest_drop);
+        assert_eq!(0, analyze(&[], &AnalyzeOptions::default()).depths);
     }
 }
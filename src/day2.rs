@@ -1,17 +1,24 @@
+use crate::parse::{self, Line, ParseResult};
+use crate::solution::{Solution, SolveResult};
+use std::error::Error;
+use std::fmt;
 use std::str::FromStr;
 
-type Distance = i32;
+const DAY: u32 = 2;
 
-#[derive(Debug, PartialEq)]
+pub type Distance = i32;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
 pub struct Position {
-    horizontal: Distance,
-    depth: Distance,
-    aim: Distance,
+    pub horizontal: Distance,
+    pub depth: Distance,
+    pub aim: Distance,
 }
 
 impl Position {
-    pub fn product(&self) -> i32 {
-        self.horizontal * self.depth
+    /// In i64, since real answers come close to `Distance::MAX`
+    pub fn product(&self) -> i64 {
+        i64::from(self.horizontal) * i64::from(self.depth)
     }
 }
 
@@ -21,24 +28,150 @@ const START_POSITION: Position = Position {
     aim: 0,
 };
 
-enum Step {
+/// One line of the planned course, such as `forward 5`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Command {
     Forward(Distance),
     Down(Distance),
     Up(Distance),
 }
 
-impl Step {
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CommandError {
+    MissingStepType,
+    MissingDistance,
+    BadDistance(String),
+    UnknownStepType(String),
+    UnexpectedText(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::MissingStepType => write!(f, "missing step type"),
+            CommandError::MissingDistance => write!(f, "missing distance"),
+            CommandError::BadDistance(text) => write!(f, "cannot parse '{}'", text),
+            CommandError::UnknownStepType(text) => write!(f, "unknown step type '{}'", text),
+            CommandError::UnexpectedText(_) => write!(f, "unexpected text after distance"),
+        }
+    }
+}
+
+impl Error for CommandError {}
+
+/// How a command moves the submarine. Each model picks the state it tracks
+/// along the course, and takes `&mut self` so it can also carry state of
+/// its own from step to step.
+pub trait NavigationModel {
+    type State: Copy;
+
+    fn start(&self) -> Self::State;
+
+    fn apply(&mut self, command: &Command, state: Self::State) -> Self::State;
+
+    /// As `apply`, but `None` where the arithmetic would overflow. Used by
+    /// `navigate_validated`. The default is unchecked: it always returns
+    /// `apply`'s result, so models that can overflow should override it.
+    fn checked_apply(&mut self, command: &Command, state: Self::State) -> Option<Self::State> {
+        Some(self.apply(command, state))
+    }
+}
+
+impl<M: NavigationModel + ?Sized> NavigationModel for &mut M {
+    type State = M::State;
+
+    fn start(&self) -> M::State {
+        (**self).start()
+    }
+
+    fn apply(&mut self, command: &Command, state: M::State) -> M::State {
+        (**self).apply(command, state)
+    }
+
+    fn checked_apply(&mut self, command: &Command, state: M::State) -> Option<M::State> {
+        (**self).checked_apply(command, state)
+    }
+}
+
+/// Part 1: up and down change depth directly
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Simple;
+
+impl NavigationModel for Simple {
+    type State = Position;
+
+    fn start(&self) -> Position {
+        START_POSITION
+    }
+
+    fn apply(&mut self, command: &Command, position: Position) -> Position {
+        command.apply_simple(position)
+    }
+
+    fn checked_apply(&mut self, command: &Command, start: Position) -> Option<Position> {
+        Some(match command {
+            Command::Forward(distance) => Position {
+                horizontal: start.horizontal.checked_add(*distance)?,
+                ..start
+            },
+            Command::Down(distance) => Position {
+                depth: start.depth.checked_add(*distance)?,
+                ..start
+            },
+            Command::Up(distance) => Position {
+                depth: start.depth.checked_sub(*distance)?,
+                ..start
+            },
+        })
+    }
+}
+
+/// Part 2: up and down change aim, and forward dives along it
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WithAim;
+
+impl NavigationModel for WithAim {
+    type State = Position;
+
+    fn start(&self) -> Position {
+        START_POSITION
+    }
+
+    fn apply(&mut self, command: &Command, position: Position) -> Position {
+        command.apply_with_aim(position)
+    }
+
+    fn checked_apply(&mut self, command: &Command, start: Position) -> Option<Position> {
+        Some(match command {
+            Command::Forward(dist) => Position {
+                horizontal: start.horizontal.checked_add(*dist)?,
+                depth: start.depth.checked_add(start.aim.checked_mul(*dist)?)?,
+                ..start
+            },
+            Command::Down(dist) => Position {
+                aim: start.aim.checked_add(*dist)?,
+                ..start
+            },
+            Command::Up(dist) => Position {
+                aim: start.aim.checked_sub(*dist)?,
+                ..start
+            },
+        })
+    }
+}
+
+impl Command {
     fn apply_simple(&self, start: Position) -> Position {
         match self {
-            Step::Forward(distance) => Position {
+            Command::Forward(distance) => Position {
                 horizontal: start.horizontal + distance,
                 ..start
             },
-            Step::Down(distance) => Position {
+            Command::Down(distance) => Position {
                 depth: start.depth + distance,
                 ..start
             },
-            Step::Up(distance) => Position {
+            Command::Up(distance) => Position {
                 depth: start.depth - distance,
                 ..start
             },
@@ -47,72 +180,1109 @@ impl Step {
 
     fn apply_with_aim(&self, start: Position) -> Position {
         match self {
-            Step::Forward(dist) => Position {
+            Command::Forward(dist) => Position {
                 horizontal: start.horizontal + dist,
                 depth: start.depth + start.aim * dist,
                 ..start
             },
-            Step::Down(dist) => Position {
+            Command::Down(dist) => Position {
                 aim: start.aim + dist,
                 ..start
             },
-            Step::Up(dist) => Position {
+            Command::Up(dist) => Position {
                 aim: start.aim - dist,
                 ..start
             },
         }
     }
+
+    /// Parse `text`, also returning the slice of it at fault on failure
+    fn parse_tokens(text: &str) -> Result<Command, (&str, CommandError)> {
+        let end = &text[text.len()..];
+        let mut split = text.split_whitespace();
+        let step_type = split.next().ok_or((end, CommandError::MissingStepType))?;
+        let command: fn(Distance) -> Command = match step_type {
+            "forward" => Command::Forward,
+            "up" => Command::Up,
+            "down" => Command::Down,
+            _ => {
+                return Err((
+                    step_type,
+                    CommandError::UnknownStepType(step_type.to_string()),
+                ))
+            }
+        };
+        let distance_str = split.next().ok_or((end, CommandError::MissingDistance))?;
+        let distance = distance_str.parse::<Distance>().map_err(|_| {
+            (
+                distance_str,
+                CommandError::BadDistance(distance_str.to_string()),
+            )
+        })?;
+        if let Some(extra) = split.next() {
+            return Err((extra, CommandError::UnexpectedText(extra.to_string())));
+        }
+        Ok(command(distance))
+    }
 }
 
-fn parse_step_def(step_def: &String) -> Step {
-    let split = step_def.split_whitespace().collect::<Vec<&str>>();
-    let step_type = split[0];
-    let distance = i32::from_str(split[1]).unwrap();
+impl FromStr for Command {
+    type Err = CommandError;
 
-    match step_type {
-        "forward" => Step::Forward(distance),
-        "up" => Step::Up(distance),
-        "down" => Step::Down(distance),
-        _ => panic!("Unknown step type: {}", step_type),
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Command::parse_tokens(text).map_err(|(_, err)| err)
     }
 }
 
-pub fn navigate(step_defs: &Vec<String>) -> Position {
-    navigate_with_strategy(step_defs, &Step::apply_simple)
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Forward(distance) => write!(f, "forward {}", distance),
+            Command::Down(distance) => write!(f, "down {}", distance),
+            Command::Up(distance) => write!(f, "up {}", distance),
+        }
+    }
 }
 
-pub fn navigate_with_aim(step_defs: &Vec<String>) -> Position {
-    navigate_with_strategy(step_defs, &Step::apply_with_aim)
+fn parse_command(line: Line) -> ParseResult<Command> {
+    Command::parse_tokens(line.text).map_err(|(token, err)| line.error(token, err.to_string()))
 }
 
-fn navigate_with_strategy(
-    step_defs: &Vec<String>,
-    strategy: &dyn Fn(&Step, Position) -> Position,
-) -> Position {
-    step_defs
+/// Parse a course, one command per line, reporting errors by line and column
+pub fn parse_commands(input: &[String]) -> ParseResult<Vec<Command>> {
+    parse::lines(DAY, input).map(parse_command).collect()
+}
+
+pub fn navigate(commands: impl IntoIterator<Item = Command>) -> Position {
+    navigate_with_strategy(commands, Simple)
+}
+
+pub fn navigate_with_aim(commands: impl IntoIterator<Item = Command>) -> Position {
+    navigate_with_strategy(commands, WithAim)
+}
+
+/// Follow the course under any navigation model
+pub fn navigate_with_strategy<M: NavigationModel>(
+    commands: impl IntoIterator<Item = Command>,
+    mut model: M,
+) -> M::State {
+    let start = model.start();
+    commands
+        .into_iter()
+        .fold(start, |pos, command| model.apply(&command, pos))
+}
+
+/// Physical limits for `navigate_validated`. The surface is always depth 0.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub seabed: Option<Distance>,
+    /// The steepest aim allowed either way
+    pub max_aim: Option<Distance>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    AboveSurface { depth: Distance },
+    BelowSeabed { depth: Distance, seabed: Distance },
+    TooSteep { aim: Distance, max_aim: Distance },
+    Overflow,
+}
+
+/// The first command that broke the limits, which was not applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NavigationError {
+    /// Index of the command in the course, so `before` is the position
+    /// at waypoint `index`
+    pub index: usize,
+    pub command: Command,
+    pub before: Position,
+    pub violation: Violation,
+}
+
+impl fmt::Display for NavigationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "command {} ({}) from horizontal {}, depth {}, aim {} ",
+            self.index, self.command, self.before.horizontal, self.before.depth, self.before.aim
+        )?;
+        match self.violation {
+            Violation::AboveSurface { depth } => {
+                write!(f, "would rise above the surface to depth {}", depth)
+            }
+            Violation::BelowSeabed { depth, seabed } => write!(
+                f,
+                "would sink to depth {}, below the seabed at {}",
+                depth, seabed
+            ),
+            Violation::TooSteep { aim, max_aim } => {
+                write!(f, "would turn to aim {}, steeper than {}", aim, max_aim)
+            }
+            Violation::Overflow => write!(f, "overflows"),
+        }
+    }
+}
+
+impl Error for NavigationError {}
+
+/// Follow the course with checked arithmetic, stopping at the first
+/// command that would overflow or leave the water between surface and seabed
+pub fn navigate_validated(
+    commands: impl IntoIterator<Item = Command>,
+    mut model: impl NavigationModel<State = Position>,
+    limits: &Limits,
+) -> Result<Position, NavigationError> {
+    let mut position = model.start();
+    for (index, command) in commands.into_iter().enumerate() {
+        let error = |violation| NavigationError {
+            index,
+            command,
+            before: position,
+            violation,
+        };
+        let next = model
+            .checked_apply(&command, position)
+            .ok_or_else(|| error(Violation::Overflow))?;
+        if next.depth < 0 {
+            return Err(error(Violation::AboveSurface { depth: next.depth }));
+        }
+        if let Some(seabed) = limits.seabed.filter(|seabed| next.depth > *seabed) {
+            return Err(error(Violation::BelowSeabed {
+                depth: next.depth,
+                seabed,
+            }));
+        }
+        if let Some(max_aim) = limits
+            .max_aim
+            .filter(|max_aim| next.aim.unsigned_abs() > max_aim.unsigned_abs())
+        {
+            return Err(error(Violation::TooSteep {
+                aim: next.aim,
+                max_aim,
+            }));
+        }
+        position = next;
+    }
+    Ok(position)
+}
+
+/// A position along the course, after `step` commands, in whatever state
+/// the model tracks
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Waypoint<S = Position> {
+    pub step: usize,
+    pub position: S,
+}
+
+/// Every position along a course, starting from the model's start at step 0
+pub struct Trajectory<I, M: NavigationModel> {
+    commands: I,
+    model: M,
+    next: Option<Waypoint<M::State>>,
+}
+
+pub fn trajectory<I: IntoIterator<Item = Command>>(commands: I) -> Trajectory<I::IntoIter, Simple> {
+    trajectory_with_strategy(commands, Simple)
+}
+
+pub fn trajectory_with_aim<I: IntoIterator<Item = Command>>(
+    commands: I,
+) -> Trajectory<I::IntoIter, WithAim> {
+    trajectory_with_strategy(commands, WithAim)
+}
+
+pub fn trajectory_with_strategy<I, M>(commands: I, model: M) -> Trajectory<I::IntoIter, M>
+where
+    I: IntoIterator<Item = Command>,
+    M: NavigationModel,
+{
+    Trajectory {
+        commands: commands.into_iter(),
+        next: Some(Waypoint {
+            step: 0,
+            position: model.start(),
+        }),
+        model,
+    }
+}
+
+impl<I: Iterator<Item = Command>, M: NavigationModel<State = Position>> Trajectory<I, M> {
+    /// The first waypoint at the deepest point of the course
+    pub fn max_depth(self) -> Option<Waypoint> {
+        self.reduce(|deepest, waypoint| {
+            if waypoint.position.depth > deepest.position.depth {
+                waypoint
+            } else {
+                deepest
+            }
+        })
+    }
+
+    pub fn first_deeper_than(mut self, depth: Distance) -> Option<Waypoint> {
+        self.find(|waypoint| waypoint.position.depth > depth)
+    }
+
+    /// Where the submarine is after `steps` commands, if the course is that long
+    pub fn after(mut self, steps: usize) -> Option<Position> {
+        self.nth(steps).map(|waypoint| waypoint.position)
+    }
+}
+
+impl<I: Iterator<Item = Command>, M: NavigationModel> Iterator for Trajectory<I, M> {
+    type Item = Waypoint<M::State>;
+
+    fn next(&mut self) -> Option<Waypoint<M::State>> {
+        let current = self.next.take()?;
+        if let Some(command) = self.commands.next() {
+            self.next = Some(Waypoint {
+                step: current.step + 1,
+                position: self.model.apply(&command, current.position),
+            });
+        }
+        Some(current)
+    }
+}
+
+pub fn trajectory_csv(waypoints: &[Waypoint]) -> String {
+    let mut result = String::from("step,horizontal,depth,aim\n");
+    for Waypoint { step, position } in waypoints {
+        result.push_str(&format!(
+            "{},{},{},{}\n",
+            step, position.horizontal, position.depth, position.aim
+        ));
+    }
+    result
+}
+
+/// The course seen side on, depth increasing downwards. Both axes are
+/// stretched to fill the picture, since depths can dwarf distances.
+pub fn trajectory_svg(waypoints: &[Waypoint]) -> String {
+    let (mut left, mut right, mut top, mut bottom) = (0, 0, 0, 0);
+    for Waypoint { position, .. } in waypoints {
+        left = left.min(position.horizontal);
+        right = right.max(position.horizontal);
+        top = top.min(position.depth);
+        bottom = bottom.max(position.depth);
+    }
+    let points = waypoints
         .iter()
-        .map(parse_step_def)
-        .fold(START_POSITION, |pos, step| strategy(&step, pos))
+        .map(|waypoint| {
+            format!(
+                "{},{}",
+                waypoint.position.horizontal, waypoint.position.depth
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"800\" height=\"400\" \
+         viewBox=\"{} {} {} {}\" preserveAspectRatio=\"none\">\n\
+         <polyline fill=\"none\" stroke=\"steelblue\" stroke-width=\"2\" \
+         vector-effect=\"non-scaling-stroke\" points=\"{}\"/>\n\
+         </svg>\n",
+        left,
+        top,
+        (right - left).max(1),
+        (bottom - top).max(1),
+        points
+    )
+}
+
+/// Courses that reach a target: the inverse of `navigate` and
+/// `navigate_with_aim`
+///
+/// Every planned command moves a positive distance, at most `max_distance`
+/// when there is a limit. Simple plans use as few commands as possible;
+/// plans with aim usually do.
+pub mod plan {
+    use super::{Command, Distance};
+    use std::collections::HashSet;
+    use std::error::Error;
+    use std::fmt;
+
+    /// Where the course should end. Only the aim model can change aim; it is
+    /// free to end anywhere when `aim` is `None`.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct Target {
+        pub horizontal: Distance,
+        pub depth: Distance,
+        pub aim: Option<Distance>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum PlanError {
+        BadLimit(Distance),
+        Behind(Distance),
+        DepthWithoutForward(Distance),
+        AimNeverChanges(Distance),
+    }
+
+    impl fmt::Display for PlanError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                PlanError::BadLimit(limit) => {
+                    write!(f, "distance limit must be at least 1, found {}", limit)
+                }
+                PlanError::Behind(horizontal) => write!(
+                    f,
+                    "cannot reach horizontal {}: commands only move forward",
+                    horizontal
+                ),
+                PlanError::DepthWithoutForward(depth) => write!(
+                    f,
+                    "cannot reach depth {} without moving forward: with aim, only forward changes depth",
+                    depth
+                ),
+                PlanError::AimNeverChanges(aim) => write!(
+                    f,
+                    "cannot reach aim {}: the simple model never changes aim",
+                    aim
+                ),
+            }
+        }
+    }
+
+    impl Error for PlanError {}
+
+    /// The shortest course to `target` under the simple model
+    pub fn plan_simple(
+        target: &Target,
+        max_distance: Option<Distance>,
+    ) -> Result<Vec<Command>, PlanError> {
+        let limit = Limit::new(max_distance)?;
+        if target.horizontal < 0 {
+            return Err(PlanError::Behind(target.horizontal));
+        }
+        if let Some(aim) = target.aim.filter(|aim| *aim != 0) {
+            return Err(PlanError::AimNeverChanges(aim));
+        }
+
+        let mut commands = Vec::new();
+        limit.forward(i128::from(target.horizontal), &mut commands);
+        limit.vertical(i128::from(target.depth), &mut commands);
+        Ok(commands)
+    }
+
+    /// A short course to `target` under the aim model, found heuristically
+    ///
+    /// Depth is the sum of aim times distance over the forward commands.
+    /// Plans set the aim before the first forward, change it at most once
+    /// along the way and once more after the last forward for the final
+    /// aim. Within 64 forward every turning point is tried, so no course of
+    /// that shape is shorter; further on only a few likely ones are: one
+    /// step from either end, where the remaining distance divides the depth
+    /// or the depth still to make up after the final aim, and one or two
+    /// full forwards from either end. Courses that turn more often, or elsewhere, are
+    /// sometimes a command or two shorter. The search time depends on the
+    /// plan's length, not on the distance.
+    pub fn short_plan_with_aim(
+        target: &Target,
+        max_distance: Option<Distance>,
+    ) -> Result<Vec<Command>, PlanError> {
+        let limit = Limit::new(max_distance)?;
+        let horizontal = i128::from(target.horizontal);
+        let depth = i128::from(target.depth);
+        let aim = target.aim.map(i128::from);
+        if horizontal < 0 {
+            return Err(PlanError::Behind(target.horizontal));
+        }
+        if horizontal == 0 {
+            if depth != 0 {
+                return Err(PlanError::DepthWithoutForward(target.depth));
+            }
+            let mut commands = Vec::new();
+            limit.vertical(aim.unwrap_or(0), &mut commands);
+            return Ok(commands);
+        }
+
+        let mut search = Search {
+            horizontal,
+            depth,
+            aim,
+            limit,
+            best: None,
+        };
+        search.run();
+        let (_, changes) = search.best.expect("every target ahead is reachable");
+
+        let mut commands = Vec::new();
+        let mut remaining = horizontal;
+        for change in changes {
+            limit.forward(remaining - change.remaining, &mut commands);
+            limit.vertical(change.aim, &mut commands);
+            remaining = change.remaining;
+        }
+        limit.forward(remaining, &mut commands);
+        Ok(commands)
+    }
+
+    /// The longest single command, in i128 so searching can't overflow
+    #[derive(Clone, Copy)]
+    struct Limit(i128);
+
+    impl Limit {
+        fn new(max_distance: Option<Distance>) -> Result<Limit, PlanError> {
+            match max_distance {
+                Some(limit) if limit < 1 => Err(PlanError::BadLimit(limit)),
+                Some(limit) => Ok(Limit(i128::from(limit))),
+                None => Ok(Limit(i128::from(Distance::MAX))),
+            }
+        }
+
+        /// Commands needed to move `distance` either way
+        fn commands(self, distance: i128) -> i128 {
+            (distance.abs() + self.0 - 1) / self.0
+        }
+
+        fn split(self, distance: i128, commands: &mut Vec<Command>, make: fn(Distance) -> Command) {
+            let mut left = distance.abs();
+            while left > 0 {
+                let chunk = left.min(self.0);
+                commands.push(make(Distance::try_from(chunk).unwrap()));
+                left -= chunk;
+            }
+        }
+
+        fn forward(self, distance: i128, commands: &mut Vec<Command>) {
+            self.split(distance, commands, Command::Forward);
+        }
+
+        /// Down for positive distances, up for negative ones
+        fn vertical(self, distance: i128, commands: &mut Vec<Command>) {
+            let make = if distance > 0 {
+                Command::Down
+            } else {
+                Command::Up
+            };
+            self.split(distance, commands, make);
+        }
+    }
+
+    /// Change aim by `aim` with `remaining` horizontal distance still to go
+    #[derive(Debug, Clone, Copy)]
+    struct Change {
+        remaining: i128,
+        aim: i128,
+    }
+
+    struct Search {
+        horizontal: i128,
+        depth: i128,
+        aim: Option<i128>,
+        limit: Limit,
+        /// Command count and aim changes of the shortest plan so far
+        best: Option<(i128, Vec<Change>)>,
+    }
+
+    impl Search {
+        fn run(&mut self) {
+            self.consider(&[]);
+            if self.depth != 0 {
+                // turning by the whole depth one step before the end always
+                // works, so there is a plan to beat from the start
+                self.consider(&[Change {
+                    remaining: 1,
+                    aim: self.depth,
+                }]);
+            }
+            for remaining in self.turning_points() {
+                if self.best_cost() <= self.lower_bound() {
+                    return;
+                }
+                self.turn_at(remaining);
+            }
+        }
+
+        /// Where to try turning, in order, each at most once
+        fn turning_points(&self) -> Vec<i128> {
+            let h = self.horizontal;
+            if h <= EXHAUSTIVE {
+                return (1..h).collect();
+            }
+            let mut points = vec![1, h - 1, self.depth.rem_euclid(h)];
+            points.extend(divisors_below(self.depth, h));
+            if let Some(aim) = self.aim {
+                // a turn at `r` needs no final change when `h - r` divides
+                // the depth the final aim would add beyond the target
+                let rest = divisors_below(aim * h - self.depth, h);
+                points.extend(rest.into_iter().map(|d| h - d));
+            }
+            for steps in 1..=2 {
+                let reach = steps * self.limit.0;
+                points.extend([reach, h - reach]);
+            }
+            let mirrored: Vec<_> = points.iter().map(|&r| h - r).collect();
+            points.extend(mirrored);
+            points.retain(|&r| 0 < r && r < h);
+            let mut seen = HashSet::new();
+            points.retain(|&r| seen.insert(r));
+            points
+        }
+
+        /// Consider every turn worth making with `remaining` still to go
+        ///
+        /// The turns that reach the target depth form a sequence
+        /// `first + j * step`, with the start and end changes linear in `j`
+        /// too. A change's command count only grows away from its zero, so
+        /// the `j` where it stays within some number of commands form an
+        /// interval, and a shortest plan starts one of those intervals.
+        fn turn_at(&mut self, remaining: i128) {
+            let (h, r) = (self.horizontal, remaining);
+            let spare = self.best_cost() - self.limit.commands(h - r) - self.limit.commands(r) - 1;
+            let g = gcd(r, h);
+            if spare < 0 || self.depth % g != 0 {
+                return;
+            }
+            let (step, shift) = (h / g, r / g);
+            let first = (self.depth / g).rem_euclid(step) * mod_inverse(shift % step, step) % step;
+            let start = (self.depth - first * r) / h;
+
+            // (value at j = 0, change per step of j) for each aim change
+            let mut costs = vec![(first, step), (start, -shift)];
+            if let Some(aim) = self.aim {
+                costs.push((aim - start - first, shift - step));
+            }
+            for &(value, slope) in &costs {
+                for commands in 0..=spare {
+                    let reach = commands * self.limit.0;
+                    let (low, high) = if slope > 0 {
+                        (
+                            div_ceil(-reach - value, slope),
+                            (reach - value).div_euclid(slope),
+                        )
+                    } else {
+                        (
+                            div_ceil(value - reach, -slope),
+                            (value + reach).div_euclid(-slope),
+                        )
+                    };
+                    if low > high {
+                        continue;
+                    }
+                    let turn = first + low * step;
+                    let turn = if turn == 0 { turn + step } else { turn };
+                    self.consider(&[Change {
+                        remaining,
+                        aim: turn,
+                    }]);
+                }
+            }
+        }
+
+        /// Complete a plan from its turns part way along, keeping it if shortest
+        fn consider(&mut self, turns: &[Change]) {
+            let rest = self.depth - turns.iter().map(|t| t.aim * t.remaining).sum::<i128>();
+            if rest % self.horizontal != 0 {
+                return;
+            }
+            let start = rest / self.horizontal;
+            let turned = start + turns.iter().map(|t| t.aim).sum::<i128>();
+            let end = self.aim.map_or(0, |aim| aim - turned);
+
+            let limit = self.limit;
+            let mut cost = limit.commands(start) + limit.commands(end);
+            let mut remaining = self.horizontal;
+            for turn in turns {
+                cost += limit.commands(remaining - turn.remaining) + limit.commands(turn.aim);
+                remaining = turn.remaining;
+            }
+            cost += limit.commands(remaining);
+
+            if cost < self.best_cost() {
+                let mut changes = vec![Change {
+                    remaining: self.horizontal,
+                    aim: start,
+                }];
+                changes.extend_from_slice(turns);
+                changes.push(Change {
+                    remaining: 0,
+                    aim: end,
+                });
+                changes.retain(|change| change.aim != 0);
+                self.best = Some((cost, changes));
+            }
+        }
+
+        fn best_cost(&self) -> i128 {
+            self.best.as_ref().map_or(i128::MAX, |(cost, _)| *cost)
+        }
+
+        /// No plan is shorter: forwards must cover the distance, each change
+        /// adds at most limit times the distance to depth, and the changes
+        /// must add up to the final aim
+        fn lower_bound(&self) -> i128 {
+            let limit = self.limit;
+            let reach = limit.0 * self.horizontal;
+            let for_depth = (self.depth.abs() + reach - 1) / reach;
+            let for_aim = self.aim.map_or(0, |aim| limit.commands(aim));
+            limit.commands(self.horizontal) + for_depth.max(for_aim)
+        }
+    }
+
+    /// Distances up to which `short_plan_with_aim` tries every turning point
+    const EXHAUSTIVE: i128 = 64;
+
+    /// Trial divisors beyond this are only found as cofactors of smaller ones
+    const TRIALS: i128 = 1 << 16;
+
+    /// Positive divisors of `n` below `below`, all of them when `n` is
+    /// below `TRIALS` squared
+    fn divisors_below(n: i128, below: i128) -> Vec<i128> {
+        let n = n.abs();
+        let mut divisors = Vec::new();
+        let mut d = 1;
+        while d <= TRIALS && d * d <= n {
+            if n % d == 0 {
+                divisors.extend([d, n / d].into_iter().filter(|&d| d < below));
+            }
+            d += 1;
+        }
+        divisors
+    }
+
+    /// Smallest integer at least `a / b`, for positive `b`
+    fn div_ceil(a: i128, b: i128) -> i128 {
+        -(-a).div_euclid(b)
+    }
+
+    fn gcd(a: i128, b: i128) -> i128 {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+
+    /// `x` such that `value * x` is 1 modulo `modulus`, for coprime arguments
+    fn mod_inverse(value: i128, modulus: i128) -> i128 {
+        let (mut old_r, mut r) = (value, modulus);
+        let (mut old_s, mut s) = (1, 0);
+        while r != 0 {
+            let quotient = old_r / r;
+            (old_r, r) = (r, old_r - quotient * r);
+            (old_s, s) = (s, old_s - quotient * s);
+        }
+        old_s.rem_euclid(modulus)
+    }
+}
+
+/// Course scripts: day 2 commands with comments, repeats, macros and includes
+///
+/// ```text
+/// # a comment, here or after any line
+/// macro zigzag {
+///     down 2
+///     forward 1
+///     up 2
+/// }
+/// repeat 3 {
+///     zigzag
+///     forward 5
+/// }
+/// include more.txt
+/// ```
+///
+/// Blocks open with `{` at the end of a line and close with `}` on a line
+/// of its own. Macros are defined at the top level of a file and used by
+/// name on a line of their own once defined; included files share them.
+/// Include paths are relative to the including file. A `#` begins a
+/// comment at the start of a line or after whitespace, so one inside a
+/// path is part of the path.
+pub mod script {
+    use super::{Command, CommandError};
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::fmt;
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
+
+    const KEYWORDS: [&str; 6] = ["forward", "down", "up", "repeat", "macro", "include"];
+
+    #[derive(Debug)]
+    pub struct ScriptError {
+        pub path: PathBuf,
+        /// 1-based line in `path`, or `None` when the whole file is at fault
+        pub line: Option<usize>,
+        pub kind: ScriptErrorKind,
+    }
+
+    #[derive(Debug)]
+    pub enum ScriptErrorKind {
+        Read(io::Error),
+        IncludeCycle(PathBuf),
+        Command(CommandError),
+        Missing(&'static str),
+        BadRepeatCount(String),
+        ExpectedBrace(String),
+        UnknownMacro(String),
+        DuplicateMacro(String),
+        ReservedName(String),
+        NestedMacro(String),
+        Unclosed,
+        UnexpectedClose,
+    }
+
+    impl fmt::Display for ScriptError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self.line {
+                Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.kind),
+                None => write!(f, "{}: {}", self.path.display(), self.kind),
+            }
+        }
+    }
+
+    impl fmt::Display for ScriptErrorKind {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                ScriptErrorKind::Read(err) => write!(f, "cannot read script: {}", err),
+                ScriptErrorKind::IncludeCycle(path) => {
+                    write!(f, "{} includes itself", path.display())
+                }
+                ScriptErrorKind::Command(err) => write!(f, "{}", err),
+                ScriptErrorKind::Missing(what) => write!(f, "missing {}", what),
+                ScriptErrorKind::BadRepeatCount(count) => {
+                    write!(f, "bad repeat count: {}", count)
+                }
+                ScriptErrorKind::ExpectedBrace(found) => {
+                    write!(f, "expected `{{` at the end of the line, found {}", found)
+                }
+                ScriptErrorKind::UnknownMacro(name) => write!(f, "unknown macro: {}", name),
+                ScriptErrorKind::DuplicateMacro(name) => {
+                    write!(f, "macro {} is already defined", name)
+                }
+                ScriptErrorKind::ReservedName(name) => {
+                    write!(f, "{} is a keyword and can't name a macro", name)
+                }
+                ScriptErrorKind::NestedMacro(name) => {
+                    write!(f, "macro {} must be defined at the top level", name)
+                }
+                ScriptErrorKind::Unclosed => write!(f, "block is never closed"),
+                ScriptErrorKind::UnexpectedClose => write!(f, "`}}` without an open block"),
+            }
+        }
+    }
+
+    impl Error for ScriptError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            match &self.kind {
+                ScriptErrorKind::Read(err) => Some(err),
+                ScriptErrorKind::Command(err) => Some(err),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    enum Statement {
+        Step(Command),
+        /// Macro uses are blocks repeated once
+        Repeat(u64, Rc<[Statement]>),
+    }
+
+    /// A parsed script, with macros and includes already resolved
+    #[derive(Debug)]
+    pub struct Script {
+        body: Vec<Statement>,
+    }
+
+    impl Script {
+        pub fn load(path: impl AsRef<Path>) -> Result<Script, ScriptError> {
+            let mut loader = Loader::default();
+            let body = loader.include(path.as_ref(), None)?;
+            Ok(Script { body })
+        }
+
+        /// Parse `text` as if read from `path`, which names it in errors
+        /// and anchors its includes
+        pub fn parse(path: impl AsRef<Path>, text: &str) -> Result<Script, ScriptError> {
+            let path = path.as_ref();
+            let mut loader = Loader {
+                including: vec![canonical(path)],
+                ..Loader::default()
+            };
+            let body = loader.parse(path, text)?;
+            Ok(Script { body })
+        }
+
+        /// The commands the script expands to, generated as they're needed
+        /// so large repeat counts cost no memory
+        pub fn steps(&self) -> Steps<'_> {
+            Steps {
+                stack: vec![(&self.body, 0, 1)],
+            }
+        }
+    }
+
+    impl<'a> IntoIterator for &'a Script {
+        type Item = Command;
+        type IntoIter = Steps<'a>;
+
+        fn into_iter(self) -> Steps<'a> {
+            self.steps()
+        }
+    }
+
+    pub struct Steps<'a> {
+        /// Each open block, the next statement in it and the passes left
+        stack: Vec<(&'a [Statement], usize, u64)>,
+    }
+
+    impl<'a> Iterator for Steps<'a> {
+        type Item = Command;
+
+        fn next(&mut self) -> Option<Command> {
+            loop {
+                let (block, next, passes) = self.stack.last_mut()?;
+                let block: &'a [Statement] = block;
+                if let Some(statement) = block.get(*next) {
+                    *next += 1;
+                    match statement {
+                        Statement::Step(command) => return Some(*command),
+                        Statement::Repeat(count, body) if *count > 0 && !body.is_empty() => {
+                            self.stack.push((body, 0, *count));
+                        }
+                        Statement::Repeat(..) => {}
+                    }
+                } else if *passes > 1 {
+                    *passes -= 1;
+                    *next = 0;
+                } else {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct Loader {
+        macros: HashMap<String, Rc<[Statement]>>,
+        /// Files being read, outermost first, to catch include cycles
+        including: Vec<PathBuf>,
+    }
+
+    /// One line of a script file, without its comment
+    struct ScriptLine<'a> {
+        number: usize,
+        text: &'a str,
+    }
+
+    impl Loader {
+        /// Read and parse `path`; `from` is the file and line including it
+        fn include(
+            &mut self,
+            path: &Path,
+            from: Option<(&Path, usize)>,
+        ) -> Result<Vec<Statement>, ScriptError> {
+            let error = |kind| match from {
+                Some((file, line)) => ScriptError {
+                    path: file.to_path_buf(),
+                    line: Some(line),
+                    kind,
+                },
+                None => ScriptError {
+                    path: path.to_path_buf(),
+                    line: None,
+                    kind,
+                },
+            };
+            let key = canonical(path);
+            if self.including.contains(&key) {
+                return Err(error(ScriptErrorKind::IncludeCycle(path.to_path_buf())));
+            }
+            let text = fs::read_to_string(path).map_err(|err| error(ScriptErrorKind::Read(err)))?;
+
+            self.including.push(key);
+            let body = self.parse(path, &text);
+            self.including.pop();
+            body
+        }
+
+        fn parse(&mut self, path: &Path, text: &str) -> Result<Vec<Statement>, ScriptError> {
+            let lines = text
+                .lines()
+                .enumerate()
+                .map(|(index, text)| ScriptLine {
+                    number: index + 1,
+                    text: without_comment(text).trim(),
+                })
+                .collect::<Vec<ScriptLine>>();
+            let mut cursor = 0;
+            self.block(path, &lines, &mut cursor, None)
+        }
+
+        /// Parse statements up to the end of the block opened on line
+        /// `opened`, or to the end of the file at the top level
+        fn block(
+            &mut self,
+            path: &Path,
+            lines: &[ScriptLine],
+            cursor: &mut usize,
+            opened: Option<usize>,
+        ) -> Result<Vec<Statement>, ScriptError> {
+            let mut body = Vec::new();
+            loop {
+                let Some(line) = lines.get(*cursor) else {
+                    return match opened {
+                        Some(number) => Err(at(path, number, ScriptErrorKind::Unclosed)),
+                        None => Ok(body),
+                    };
+                };
+                *cursor += 1;
+                let error = |kind| at(path, line.number, kind);
+                let mut words = line.text.split_whitespace();
+                let Some(first) = words.next() else { continue };
+
+                match first {
+                    "}" if line.text == "}" => {
+                        return match opened {
+                            Some(_) => Ok(body),
+                            None => Err(error(ScriptErrorKind::UnexpectedClose)),
+                        };
+                    }
+                    "repeat" => {
+                        let count = words
+                            .next()
+                            .ok_or_else(|| error(ScriptErrorKind::Missing("repeat count")))?;
+                        let count = count.parse::<u64>().map_err(|_| {
+                            error(ScriptErrorKind::BadRepeatCount(count.to_string()))
+                        })?;
+                        open_brace(words).map_err(error)?;
+                        let inner = self.block(path, lines, cursor, Some(line.number))?;
+                        body.push(Statement::Repeat(count, inner.into()));
+                    }
+                    "macro" => {
+                        let name = words
+                            .next()
+                            .ok_or_else(|| error(ScriptErrorKind::Missing("macro name")))?;
+                        open_brace(words).map_err(error)?;
+                        if opened.is_some() {
+                            return Err(error(ScriptErrorKind::NestedMacro(name.to_string())));
+                        }
+                        if KEYWORDS.contains(&name) {
+                            return Err(error(ScriptErrorKind::ReservedName(name.to_string())));
+                        }
+                        if self.macros.contains_key(name) {
+                            return Err(error(ScriptErrorKind::DuplicateMacro(name.to_string())));
+                        }
+                        let inner = self.block(path, lines, cursor, Some(line.number))?;
+                        self.macros.insert(name.to_string(), inner.into());
+                    }
+                    "include" => {
+                        let target = line.text["include".len()..].trim();
+                        if target.is_empty() {
+                            return Err(error(ScriptErrorKind::Missing("include path")));
+                        }
+                        let target = path.parent().unwrap_or(Path::new("")).join(target);
+                        body.extend(self.include(&target, Some((path, line.number)))?);
+                    }
+                    name if words.next().is_none() && !KEYWORDS.contains(&name) => {
+                        let inner = self.macros.get(name).ok_or_else(|| {
+                            error(ScriptErrorKind::UnknownMacro(name.to_string()))
+                        })?;
+                        body.push(Statement::Repeat(1, Rc::clone(inner)));
+                    }
+                    _ => {
+                        let command = line
+                            .text
+                            .parse::<Command>()
+                            .map_err(|err| error(ScriptErrorKind::Command(err)))?;
+                        body.push(Statement::Step(command));
+                    }
+                }
+            }
+        }
+    }
+
+    /// A `#` starts a comment only at the start of a line or after
+    /// whitespace, so one inside a word such as an include path is kept
+    fn without_comment(line: &str) -> &str {
+        let start = line.char_indices().find(|&(index, c)| {
+            c == '#'
+                && line[..index]
+                    .chars()
+                    .next_back()
+                    .map_or(true, char::is_whitespace)
+        });
+        match start {
+            Some((index, _)) => &line[..index],
+            None => line,
+        }
+    }
+
+    /// The rest of a block's opening line must be exactly `{`
+    fn open_brace<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<(), ScriptErrorKind> {
+        match (words.next(), words.next()) {
+            (Some("{"), None) => Ok(()),
+            (None, _) => Err(ScriptErrorKind::ExpectedBrace(
+                "the end of the line".to_string(),
+            )),
+            (Some("{"), Some(extra)) | (Some(extra), _) => {
+                Err(ScriptErrorKind::ExpectedBrace(extra.to_string()))
+            }
+        }
+    }
+
+    fn at(path: &Path, line: usize, kind: ScriptErrorKind) -> ScriptError {
+        ScriptError {
+            path: path.to_path_buf(),
+            line: Some(line),
+            kind,
+        }
+    }
+
+    /// Paths compare by their canonical form when the file exists
+    fn canonical(path: &Path) -> PathBuf {
+        fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    }
+}
+
+pub struct Day2 {
+    commands: Vec<Command>,
+}
+
+impl Solution for Day2 {
+    fn parse(input: &[String]) -> ParseResult<Self> {
+        Ok(Day2 {
+            commands: parse_commands(input)?,
+        })
+    }
+
+    fn part1(&self) -> SolveResult {
+        Ok(navigate(self.commands.iter().copied()).product().into())
+    }
+
+    fn part2(&self) -> Option<SolveResult> {
+        Some(Ok(navigate_with_aim(self.commands.iter().copied())
+            .product()
+            .into()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::navigate;
-    use super::Position;
+    use super::plan::{plan_simple, short_plan_with_aim, PlanError, Target};
+    use super::script::{Script, ScriptErrorKind};
+    use super::*;
+    use crate::examples;
+    use crate::solution::Answer;
+    use std::collections::{HashMap, HashSet};
+    use Command::*;
+
+    fn example_input() -> Vec<String> {
+        let (input, _) = examples::load(&examples::default_fixtures_dir(), DAY).unwrap();
+        input
+    }
+
+    fn example() -> Vec<Command> {
+        parse_commands(&example_input()).unwrap()
+    }
 
     #[test]
     fn applies_steps() {
-        let step_defs = [
-            "forward 5",
-            "down 5",
-            "forward 8",
-            "up 3",
-            "down 8",
-            "forward 2",
-        ]
-        .map(|s| s.to_string())
-        .to_vec();
-        let end_pos = navigate(&step_defs);
+        let end_pos = navigate(parse_commands(&example_input()).unwrap());
 
         assert_eq!(
             end_pos,
@@ -124,4 +1294,626 @@ mod tests {
         );
         assert_eq!(end_pos.product(), 150);
     }
+
+    #[test]
+    fn multiplies_without_overflow() {
+        let input = vec![
+            "forward 2000000000".to_string(),
+            "down 2000000000".to_string(),
+        ];
+        let day2 = Day2::parse(&input).unwrap();
+        assert_eq!(Ok(Answer::Number(4_000_000_000_000_000_000)), day2.part1());
+
+        let end = Position {
+            horizontal: Distance::MAX,
+            depth: Distance::MIN,
+            aim: 0,
+        };
+        assert_eq!(-(1 << 62) + (1 << 31), end.product());
+    }
+
+    #[test]
+    fn reports_unknown_step_type() {
+        let step_defs = ["forward 5", "sideways 3"].map(String::from);
+        let err = parse_commands(&step_defs).unwrap_err();
+
+        assert_eq!((2, 2, 1), (err.day, err.line, err.column));
+        assert_eq!("sideways", err.text);
+    }
+
+    #[test]
+    fn reports_bad_distance() {
+        let step_defs = ["down x"].map(String::from);
+        let err = parse_commands(&step_defs).unwrap_err();
+
+        assert_eq!((1, 6), (err.line, err.column));
+        assert_eq!("x", err.text);
+    }
+
+    #[test]
+    fn parses_and_displays_commands() {
+        let command = "down 8".parse::<Command>().unwrap();
+        assert_eq!(Command::Down(8), command);
+        assert_eq!("down 8", command.to_string());
+        assert_eq!(Ok(Command::Forward(2)), "  forward   2 ".parse());
+    }
+
+    #[test]
+    fn returns_typed_command_errors() {
+        assert_eq!(Err(CommandError::MissingStepType), "".parse::<Command>());
+        assert_eq!(Err(CommandError::MissingDistance), "up".parse::<Command>());
+        assert_eq!(
+            Err(CommandError::BadDistance("x".to_string())),
+            "up x".parse::<Command>()
+        );
+        assert_eq!(
+            Err(CommandError::UnknownStepType("sideways".to_string())),
+            "sideways 3".parse::<Command>()
+        );
+        assert_eq!(
+            Err(CommandError::UnknownStepType("sideways".to_string())),
+            "sideways x".parse::<Command>()
+        );
+        assert_eq!(
+            Err(CommandError::UnexpectedText("now".to_string())),
+            "up 3 now".parse::<Command>()
+        );
+    }
+
+    #[test]
+    fn navigates_commands_built_in_code() {
+        let commands = [Command::Forward(5), Command::Down(5), Command::Forward(8)];
+        assert_eq!(
+            Position {
+                horizontal: 13,
+                depth: 40,
+                aim: 5
+            },
+            navigate_with_aim(commands)
+        );
+        assert_eq!(65, navigate(commands.iter().copied()).product());
+    }
+
+    #[test]
+    fn records_every_waypoint() {
+        let waypoints: Vec<Waypoint> = trajectory_with_aim(example()).collect();
+
+        assert_eq!(7, waypoints.len());
+        assert_eq!(
+            (0, Position::default()),
+            (waypoints[0].step, waypoints[0].position)
+        );
+        assert_eq!(
+            Waypoint {
+                step: 6,
+                position: navigate_with_aim(example())
+            },
+            waypoints[6]
+        );
+        assert_eq!(
+            vec![0, 0, 0, 40, 40, 40, 60],
+            waypoints
+                .iter()
+                .map(|w| w.position.depth)
+                .collect::<Vec<i32>>()
+        );
+    }
+
+    #[test]
+    fn queries_the_trajectory() {
+        assert_eq!(6, trajectory_with_aim(example()).max_depth().unwrap().step);
+        assert_eq!(5, trajectory(example()).max_depth().unwrap().step);
+        assert_eq!(
+            Some(3),
+            trajectory_with_aim(example())
+                .first_deeper_than(20)
+                .map(|w| w.step)
+        );
+        assert_eq!(None, trajectory(example()).first_deeper_than(10));
+        assert_eq!(
+            Some(Position {
+                horizontal: 5,
+                depth: 0,
+                aim: 5
+            }),
+            trajectory_with_aim(example()).after(2)
+        );
+        assert_eq!(None, trajectory(example()).after(7));
+    }
+
+    #[test]
+    fn exports_trajectory() {
+        let waypoints: Vec<Waypoint> = trajectory(example()).take(3).collect();
+        assert_eq!(
+            "step,horizontal,depth,aim\n0,0,0,0\n1,5,0,0\n2,5,5,0\n",
+            trajectory_csv(&waypoints)
+        );
+
+        let svg = trajectory_svg(&waypoints);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("viewBox=\"0 0 5 5\""));
+        assert!(svg.contains("points=\"0,0 5,0 5,5\""));
+    }
+
+    /// Aim-based steering with a current pushing the submarine along,
+    /// and a hull that can't go below a maximum depth
+    struct Drifting {
+        current: i32,
+        max_depth: i32,
+        steps: usize,
+    }
+
+    impl NavigationModel for Drifting {
+        type State = Position;
+
+        fn start(&self) -> Position {
+            Position::default()
+        }
+
+        fn apply(&mut self, command: &Command, position: Position) -> Position {
+            self.steps += 1;
+            let moved = WithAim.apply(command, position);
+            Position {
+                horizontal: moved.horizontal + self.current,
+                depth: moved.depth.min(self.max_depth),
+                ..moved
+            }
+        }
+    }
+
+    #[test]
+    fn plugs_in_custom_models() {
+        let mut model = Drifting {
+            current: 1,
+            max_depth: 50,
+            steps: 0,
+        };
+        let end = navigate_with_strategy(example(), &mut model);
+
+        assert_eq!(
+            Position {
+                horizontal: 21,
+                depth: 50,
+                aim: 10
+            },
+            end
+        );
+        assert_eq!(6, model.steps);
+
+        let deepest = trajectory_with_strategy(example(), &mut model).max_depth();
+        assert_eq!(Some(50), deepest.map(|w| w.position.depth));
+
+        // models without their own checked_apply validate unchecked
+        let validated = navigate_validated(example(), &mut model, &Limits::default());
+        assert_eq!(Ok(end), validated);
+    }
+
+    #[test]
+    fn validates_the_example_course() {
+        let limits = Limits {
+            seabed: Some(60),
+            max_aim: Some(10),
+        };
+        assert_eq!(
+            Ok(navigate_with_aim(example())),
+            navigate_validated(example(), WithAim, &limits)
+        );
+    }
+
+    #[test]
+    fn stops_at_the_surface() {
+        let course = [Command::Down(2), Command::Up(3), Command::Down(9)];
+        assert_eq!(
+            Err(NavigationError {
+                index: 1,
+                command: Command::Up(3),
+                before: Position {
+                    horizontal: 0,
+                    depth: 2,
+                    aim: 0
+                },
+                violation: Violation::AboveSurface { depth: -1 },
+            }),
+            navigate_validated(course, Simple, &Limits::default())
+        );
+    }
+
+    #[test]
+    fn stops_at_the_seabed() {
+        let limits = Limits {
+            seabed: Some(50),
+            ..Limits::default()
+        };
+        let err = navigate_validated(example(), WithAim, &limits).unwrap_err();
+        assert_eq!(5, err.index);
+        assert_eq!(40, err.before.depth);
+        assert_eq!(
+            Violation::BelowSeabed {
+                depth: 60,
+                seabed: 50
+            },
+            err.violation
+        );
+        assert_eq!(
+            "command 5 (forward 2) from horizontal 13, depth 40, aim 10 \
+             would sink to depth 60, below the seabed at 50",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn stops_at_the_steepest_aim() {
+        let limits = Limits {
+            max_aim: Some(8),
+            ..Limits::default()
+        };
+        let err = navigate_validated(example(), WithAim, &limits).unwrap_err();
+        assert_eq!(
+            (
+                4,
+                Violation::TooSteep {
+                    aim: 10,
+                    max_aim: 8
+                }
+            ),
+            (err.index, err.violation)
+        );
+        assert_eq!(
+            "command 4 (down 8) from horizontal 13, depth 40, aim 2 \
+             would turn to aim 10, steeper than 8",
+            err.to_string()
+        );
+        let climbing = [Command::Up(9)];
+        assert!(navigate_validated(climbing, WithAim, &limits).is_err());
+    }
+
+    #[test]
+    fn catches_overflow() {
+        let course = [Command::Down(i32::MAX), Command::Forward(2)];
+        let err = navigate_validated(course, WithAim, &Limits::default()).unwrap_err();
+        assert_eq!((1, Violation::Overflow), (err.index, err.violation));
+
+        let course = [Command::Up(i32::MAX), Command::Up(2)];
+        let err = navigate_validated(course, WithAim, &Limits::default()).unwrap_err();
+        assert_eq!(Violation::Overflow, err.violation);
+    }
+
+    /// Lengths of the shortest courses of up to nine commands to every
+    /// position within five forward, by breadth-first search. With `turns`,
+    /// aim changes at most that many times between forwards.
+    fn shortest_courses(limit: Distance, turns: Option<usize>) -> HashMap<Position, usize> {
+        // position, aim changes between forwards, last command changed aim,
+        // some forward already
+        let start = (Position::default(), 0, false, false);
+        let mut shortest = HashMap::from([(start.0, 0)]);
+        let mut seen = HashSet::from([start]);
+        let mut frontier = vec![start];
+        for length in 1..=9 {
+            let mut next = Vec::new();
+            for (position, turned, aiming, forwarded) in frontier {
+                for distance in 1..=limit {
+                    for command in [Forward(distance), Down(distance), Up(distance)] {
+                        let moved = WithAim.apply(&command, position);
+                        let state = match command {
+                            Forward(_) if turns.is_some_and(|turns| turned > turns) => continue,
+                            Forward(_) => (moved, turned, false, true),
+                            _ if forwarded && !aiming && turns.is_some() => {
+                                (moved, turned + 1, true, true)
+                            }
+                            _ => (moved, turned, true, forwarded),
+                        };
+                        if moved.horizontal <= 5 && seen.insert(state) {
+                            shortest.entry(moved).or_insert(length);
+                            next.push(state);
+                        }
+                    }
+                }
+            }
+            frontier = next;
+        }
+        shortest
+    }
+
+    fn assert_reaches(target: &Target, limit: Option<Distance>, course: &[Command]) {
+        let end = navigate_with_aim(course.iter().copied());
+        assert_eq!(
+            (target.horizontal, target.depth),
+            (end.horizontal, end.depth)
+        );
+        assert!(target.aim.map_or(true, |aim| end.aim == aim));
+        let within = 1..=limit.unwrap_or(Distance::MAX);
+        assert!(course.iter().all(|command| match *command {
+            Forward(distance) | Down(distance) | Up(distance) => within.contains(&distance),
+        }));
+    }
+
+    #[test]
+    fn plans_simple_courses() {
+        let target = Target {
+            horizontal: 15,
+            depth: -10,
+            aim: None,
+        };
+        assert_eq!(
+            vec![Forward(15), Up(10)],
+            plan_simple(&target, None).unwrap()
+        );
+
+        let course = plan_simple(&target, Some(4)).unwrap();
+        assert_eq!(7, course.len());
+        let end = navigate(course);
+        assert_eq!((15, -10), (end.horizontal, end.depth));
+    }
+
+    #[test]
+    fn plans_courses_with_aim() {
+        let target = Target {
+            horizontal: 15,
+            depth: 60,
+            aim: Some(10),
+        };
+        let course = short_plan_with_aim(&target, None).unwrap();
+        assert_eq!(3, course.len());
+        assert_eq!(navigate_with_aim(example()), navigate_with_aim(course));
+
+        let target = Target {
+            horizontal: 1911,
+            depth: 847_654,
+            aim: Some(1000),
+        };
+        let course = short_plan_with_aim(&target, Some(9)).unwrap();
+        assert!(course.len() <= 213 + 112 + 2);
+        assert_reaches(&target, Some(9), &course);
+        for limit in [Some(100), Some(1000), None] {
+            let course = short_plan_with_aim(&target, limit).unwrap();
+            assert_reaches(&target, limit, &course);
+        }
+
+        let target = Target {
+            horizontal: 10,
+            depth: 2_147_483_000,
+            aim: Some(1000),
+        };
+        assert_eq!(
+            vec![Down(214_748_300), Forward(10), Up(214_747_300)],
+            short_plan_with_aim(&target, None).unwrap()
+        );
+    }
+
+    #[test]
+    fn plans_far_courses_without_searching_every_turn() {
+        let target = Target {
+            horizontal: 2_000_000_000,
+            depth: 7,
+            aim: None,
+        };
+        assert_eq!(
+            vec![Forward(1_999_999_999), Down(7), Forward(1)],
+            short_plan_with_aim(&target, None).unwrap()
+        );
+
+        let target = Target {
+            horizontal: 100_000_000,
+            depth: 123_456_789,
+            aim: Some(-5),
+        };
+        let course = short_plan_with_aim(&target, Some(1000)).unwrap();
+        assert_reaches(&target, Some(1000), &course);
+        assert!(course.len() <= 100_000 + 3);
+    }
+
+    #[test]
+    fn plans_are_nearly_as_short_as_any_course() {
+        let mut longer = 0;
+        for limit in 1..=3 {
+            let shortest = shortest_courses(limit, None);
+            let mut any_aim = HashMap::new();
+            for (p, &length) in &shortest {
+                let known = any_aim.entry((p.horizontal, p.depth)).or_insert(length);
+                *known = length.min(*known);
+            }
+            for horizontal in 0..=5 {
+                for depth in -40..=40 {
+                    for aim in [None].into_iter().chain((-12..=12).map(Some)) {
+                        let target = Target {
+                            horizontal,
+                            depth,
+                            aim,
+                        };
+                        let Ok(course) = short_plan_with_aim(&target, Some(limit)) else {
+                            assert_eq!(0, horizontal);
+                            continue;
+                        };
+                        assert_reaches(&target, Some(limit), &course);
+                        let found = match aim {
+                            Some(aim) => shortest.get(&Position {
+                                horizontal,
+                                depth,
+                                aim,
+                            }),
+                            None => any_aim.get(&(horizontal, depth)),
+                        };
+                        match found {
+                            Some(&length) => {
+                                assert!(course.len() <= length + 1, "{:?} limit {}", target, limit);
+                                longer += usize::from(course.len() > length);
+                            }
+                            None => assert!(course.len() > 9, "{:?} limit {}", target, limit),
+                        }
+                    }
+                }
+            }
+        }
+        // of the 12,867 targets reached in nine commands, the ones where a
+        // course of another shape is a command shorter
+        assert_eq!(8, longer);
+    }
+
+    #[test]
+    fn plans_turn_as_far_as_needed() {
+        let shortest = shortest_courses(3, None);
+        for depth in [7, 14] {
+            let target = Target {
+                horizontal: 3,
+                depth,
+                aim: Some(7),
+            };
+            let course = short_plan_with_aim(&target, Some(3)).unwrap();
+            assert_reaches(&target, Some(3), &course);
+            let end = Position {
+                horizontal: 3,
+                depth,
+                aim: 7,
+            };
+            assert_eq!((5, 5), (shortest[&end], course.len()));
+        }
+    }
+
+    #[test]
+    fn explains_unreachable_targets() {
+        let behind = Target {
+            horizontal: -1,
+            ..Target::default()
+        };
+        assert_eq!(
+            Err(PlanError::Behind(-1)),
+            short_plan_with_aim(&behind, None)
+        );
+        assert_eq!(Err(PlanError::Behind(-1)), plan_simple(&behind, None));
+
+        let straight_down = Target {
+            depth: 5,
+            ..Target::default()
+        };
+        assert_eq!(
+            Err(PlanError::DepthWithoutForward(5)),
+            short_plan_with_aim(&straight_down, None)
+        );
+        assert_eq!(vec![Down(5)], plan_simple(&straight_down, None).unwrap());
+
+        let aimed = Target {
+            aim: Some(3),
+            ..Target::default()
+        };
+        assert_eq!(
+            Err(PlanError::AimNeverChanges(3)),
+            plan_simple(&aimed, None)
+        );
+        assert_eq!(
+            "cannot reach aim 3: the simple model never changes aim",
+            plan_simple(&aimed, None).unwrap_err().to_string()
+        );
+        assert_eq!(
+            Err(PlanError::BadLimit(0)),
+            short_plan_with_aim(&aimed, Some(0))
+        );
+    }
+
+    #[test]
+    fn expands_scripts() {
+        let text = "\
+# the example course, twice
+macro example {
+    forward 5
+    down 5  # dive
+    forward 8
+    up 3
+    down 8
+    forward 2
+}
+
+repeat 2 {
+    example
+}
+repeat 0 {
+    up 100
+}
+";
+        let script = Script::parse("course.txt", text).unwrap();
+        let steps = script.steps().collect::<Vec<Command>>();
+        assert_eq!([example(), example()].concat(), steps);
+
+        let once = navigate_with_strategy(example(), WithAim);
+        let twice = navigate_with_strategy(&script, WithAim);
+        assert_eq!(2 * once.horizontal, twice.horizontal);
+    }
+
+    #[test]
+    fn repeats_without_expanding_in_memory() {
+        let text = "repeat 1000000 {\nrepeat 1000000 {\nforward 1\n}\n}\n";
+        let script = Script::parse("long.txt", text).unwrap();
+        assert_eq!(Some(Forward(1)), script.steps().nth(5_000_000));
+    }
+
+    #[test]
+    fn includes_scripts_relative_to_the_includer() {
+        let dir = std::env::temp_dir().join(format!("advent2021-script-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("parts")).unwrap();
+        std::fs::write(
+            dir.join("parts/dive.txt"),
+            "macro dive {\ndown 5\n}\nforward 1\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("parts/loop.txt"), "include loop.txt\n").unwrap();
+        std::fs::write(dir.join("main.txt"), "include parts/dive.txt\ndive\n").unwrap();
+
+        let script = Script::load(dir.join("main.txt")).unwrap();
+        assert_eq!(
+            vec![Forward(1), Down(5)],
+            script.steps().collect::<Vec<_>>()
+        );
+
+        let err =
+            Script::parse(dir.join("main.txt"), "forward 1\ninclude parts/loop.txt\n").unwrap_err();
+        assert_eq!(dir.join("parts/loop.txt"), err.path);
+        assert_eq!(Some(1), err.line);
+        assert!(matches!(err.kind, ScriptErrorKind::IncludeCycle(_)));
+
+        std::fs::write(dir.join("parts/take#2.txt"), "up 2 # surface\n").unwrap();
+        let script = Script::parse(
+            dir.join("main.txt"),
+            "include parts/take#2.txt # second take\n",
+        )
+        .unwrap();
+        assert_eq!(vec![Up(2)], script.steps().collect::<Vec<_>>());
+
+        let err = Script::parse(dir.join("main.txt"), "\ninclude missing.txt").unwrap_err();
+        assert_eq!((dir.join("main.txt"), Some(2)), (err.path, err.line));
+        assert!(matches!(err.kind, ScriptErrorKind::Read(_)));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reports_script_errors_by_file_and_line() {
+        let error = |text| Script::parse("bad.txt", text).unwrap_err().to_string();
+        assert_eq!(
+            "bad.txt:2: cannot parse 'ten'",
+            error("forward 1\nforward ten")
+        );
+        assert_eq!("bad.txt:1: unknown macro: zigzag", error("zigzag\n"));
+        assert_eq!(
+            "bad.txt:2: block is never closed",
+            error("\nrepeat 3 {\nup 1")
+        );
+        assert_eq!("bad.txt:1: `}` without an open block", error("}"));
+        assert_eq!("bad.txt:1: bad repeat count: -1", error("repeat -1 {\n}"));
+        assert_eq!(
+            "bad.txt:1: expected `{` at the end of the line, found the end of the line",
+            error("repeat 2\n")
+        );
+        assert_eq!(
+            "bad.txt:2: macro inner must be defined at the top level",
+            error("repeat 2 {\nmacro inner {\n}\n}")
+        );
+        assert_eq!(
+            "bad.txt:3: macro m is already defined",
+            error("macro m {\n}\nmacro m {\n}")
+        );
+        assert_eq!(
+            "bad.txt:1: up is a keyword and can't name a macro",
+            error("macro up {\n}")
+        );
+        assert_eq!("bad.txt:2: unknown macro: m", error("macro m {\nm\n}"));
+    }
 }
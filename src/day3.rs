@@ -5,7 +5,301 @@
 //! Think of each n-digit binary string as a path through a tree of nodes,
 //! each with two children, 1 and 0. The binary string then walks the tree,
 //! incrementing each node it touches.
+use crate::parse::{self, Line, ParseResult};
+use crate::solution::{Solution, SolveResult};
+use packed::ReportError;
+
+const DAY: u32 = 3;
+
+/// Report values of any width
+///
+/// Values up to 64 bits are kept in a `u64` and up to 128 bits in a `u128`;
+/// anything wider falls back to a vector of 32-bit limbs. Arithmetic moves
+/// between them as needed, so results are always exact.
+pub mod value {
+    use crate::solution::Answer;
+    use std::fmt;
+    use std::ops::Mul;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Value(Repr);
+
+    /// Always the narrowest representation that fits, so derived equality holds
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Repr {
+        Narrow(u64),
+        Wide(u128),
+        /// Little-endian, with no trailing zero limbs
+        Big(Vec<u32>),
+    }
+
+    impl Value {
+        /// Read a binary string such as `10110`, most significant bit first
+        pub fn from_bits(bits: &str) -> Option<Value> {
+            if bits.is_empty() || !bits.bytes().all(|b| b == b'0' || b == b'1') {
+                return None;
+            }
+            let significant = bits.trim_start_matches('0');
+            if significant.len() <= 128 {
+                return Some(Value::from(u128::from_str_radix(bits, 2).unwrap_or(0)));
+            }
+            let limbs = significant
+                .as_bytes()
+                .rchunks(32)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .fold(0, |limb, b| limb << 1 | u32::from(b - b'0'))
+                })
+                .collect();
+            Some(Value::from_limbs(limbs))
+        }
+
+        pub fn to_u64(&self) -> Option<u64> {
+            match self.0 {
+                Repr::Narrow(n) => Some(n),
+                _ => None,
+            }
+        }
+
+        pub fn to_u128(&self) -> Option<u128> {
+            match self.0 {
+                Repr::Narrow(n) => Some(u128::from(n)),
+                Repr::Wide(n) => Some(n),
+                Repr::Big(_) => None,
+            }
+        }
+
+        fn limbs(&self) -> Vec<u32> {
+            match &self.0 {
+                Repr::Big(limbs) => limbs.clone(),
+                _ => {
+                    let mut n = self.to_u128().unwrap();
+                    let mut limbs = Vec::new();
+                    while n > 0 {
+                        limbs.push(n as u32);
+                        n >>= 32;
+                    }
+                    limbs
+                }
+            }
+        }
+
+        fn from_limbs(mut limbs: Vec<u32>) -> Value {
+            while limbs.last() == Some(&0) {
+                limbs.pop();
+            }
+            if limbs.len() > 4 {
+                return Value(Repr::Big(limbs));
+            }
+            let n = limbs
+                .iter()
+                .rev()
+                .fold(0, |n, limb| n << 32 | u128::from(*limb));
+            Value::from(n)
+        }
+    }
+
+    impl From<u64> for Value {
+        fn from(n: u64) -> Self {
+            Value(Repr::Narrow(n))
+        }
+    }
+
+    impl From<u128> for Value {
+        fn from(n: u128) -> Self {
+            match u64::try_from(n) {
+                Ok(n) => Value(Repr::Narrow(n)),
+                Err(_) => Value(Repr::Wide(n)),
+            }
+        }
+    }
+
+    impl Mul for &Value {
+        type Output = Value;
+
+        fn mul(self, other: &Value) -> Value {
+            match (&self.0, &other.0) {
+                (Repr::Narrow(a), Repr::Narrow(b)) => Value::from(u128::from(*a) * u128::from(*b)),
+                _ => {
+                    if let Some(n) = self
+                        .to_u128()
+                        .zip(other.to_u128())
+                        .and_then(|(a, b)| a.checked_mul(b))
+                    {
+                        return Value::from(n);
+                    }
+                    let (a, b) = (self.limbs(), other.limbs());
+                    let mut product = vec![0u32; a.len() + b.len()];
+                    for (i, x) in a.iter().enumerate() {
+                        let mut carry = 0u64;
+                        for (j, y) in b.iter().enumerate() {
+                            let sum =
+                                u64::from(product[i + j]) + u64::from(*x) * u64::from(*y) + carry;
+                            product[i + j] = sum as u32;
+                            carry = sum >> 32;
+                        }
+                        product[i + b.len()] = carry as u32;
+                    }
+                    Value::from_limbs(product)
+                }
+            }
+        }
+    }
+
+    impl fmt::Display for Value {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let mut limbs = match &self.0 {
+                Repr::Narrow(n) => return write!(f, "{}", n),
+                Repr::Wide(n) => return write!(f, "{}", n),
+                Repr::Big(limbs) => limbs.clone(),
+            };
+            // peel off nine decimal digits at a time, least significant first
+            const CHUNK: u64 = 1_000_000_000;
+            let mut chunks = Vec::new();
+            while !limbs.is_empty() {
+                let mut remainder = 0u64;
+                for limb in limbs.iter_mut().rev() {
+                    let current = remainder << 32 | u64::from(*limb);
+                    *limb = (current / CHUNK) as u32;
+                    remainder = current % CHUNK;
+                }
+                chunks.push(remainder);
+                while limbs.last() == Some(&0) {
+                    limbs.pop();
+                }
+            }
+            let mut chunks = chunks.iter().rev();
+            write!(f, "{}", chunks.next().unwrap())?;
+            chunks.try_for_each(|chunk| write!(f, "{:09}", chunk))
+        }
+    }
+
+    /// Numbers when they fit the answer type, otherwise their decimal text
+    impl From<Value> for Answer {
+        fn from(value: Value) -> Self {
+            match value.to_u64().and_then(|n| i64::try_from(n).ok()) {
+                Some(n) => Answer::Number(n),
+                None => Answer::Text(value.to_string()),
+            }
+        }
+    }
+}
+
+/// How ratings pick a bit from the counts of zeros and ones
+///
+/// The puzzle's rules are most or least common with a fixed winner on ties;
+/// `custom` takes any other rule as a closure.
+pub mod criteria {
+    use std::cmp::Ordering;
+    use std::error::Error;
+    use std::fmt;
+
+    /// What to do when zeros and ones are equally common
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Tie {
+        PreferZero,
+        PreferOne,
+        Reject,
+    }
+
+    type Choose = dyn Fn(usize, usize) -> Option<bool>;
+
+    /// How to pick a bit from the counts of zeros and ones in a column
+    #[derive(Debug)]
+    pub struct BitCriteria(Rule);
+
+    #[derive(Debug)]
+    enum Rule {
+        MostCommon(Tie),
+        LeastCommon(Tie),
+        Custom(Custom),
+    }
+
+    struct Custom(Box<Choose>);
+
+    impl fmt::Debug for Custom {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("Custom(..)")
+        }
+    }
+
+    impl BitCriteria {
+        pub fn most_common(tie: Tie) -> Self {
+            BitCriteria(Rule::MostCommon(tie))
+        }
+
+        pub fn least_common(tie: Tie) -> Self {
+            BitCriteria(Rule::LeastCommon(tie))
+        }
+
+        /// `choose` gets the counts of zeros and ones and returns whether to
+        /// pick a 1, or `None` when it can't decide
+        pub fn custom(choose: impl Fn(usize, usize) -> Option<bool> + 'static) -> Self {
+            BitCriteria(Rule::Custom(Custom(Box::new(choose))))
+        }
+
+        /// Whether to pick a 1 at 1-based `column`, given the counts there
+        pub fn choose(
+            &self,
+            column: usize,
+            zeros: usize,
+            ones: usize,
+        ) -> Result<bool, CriteriaError> {
+            let chosen = match &self.0 {
+                Rule::MostCommon(tie) => match zeros.cmp(&ones) {
+                    Ordering::Greater => Some(false),
+                    Ordering::Less => Some(true),
+                    Ordering::Equal => resolve(*tie),
+                },
+                Rule::LeastCommon(tie) => match zeros.cmp(&ones) {
+                    Ordering::Less => Some(false),
+                    Ordering::Greater => Some(true),
+                    Ordering::Equal => resolve(*tie),
+                },
+                Rule::Custom(Custom(choose)) => choose(zeros, ones),
+            };
+            chosen.ok_or(CriteriaError {
+                column,
+                zeros,
+                ones,
+            })
+        }
+    }
+
+    fn resolve(tie: Tie) -> Option<bool> {
+        match tie {
+            Tie::PreferZero => Some(false),
+            Tie::PreferOne => Some(true),
+            Tie::Reject => None,
+        }
+    }
+
+    /// The criteria couldn't pick a bit
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CriteriaError {
+        /// 1-based column of the bit being chosen
+        pub column: usize,
+        pub zeros: usize,
+        pub ones: usize,
+    }
+
+    impl fmt::Display for CriteriaError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                f,
+                "no bit chosen for column {} with {} zeros and {} ones",
+                self.column, self.zeros, self.ones
+            )
+        }
+    }
+
+    impl Error for CriteriaError {}
+}
+
 pub mod part1 {
+    use super::criteria::{BitCriteria, CriteriaError, Tie};
+    use super::value::Value;
 
     #[derive(Debug, PartialEq)]
     pub struct Rates {
@@ -14,38 +308,55 @@ pub mod part1 {
     }
 
     impl Rates {
-        pub fn product(&self) -> i32 {
-            let parse = |value| i32::from_str_radix(value, 2).unwrap();
-            let gamma = parse(&self.gamma);
-            let epsilon = parse(&self.epsilon);
-            gamma * epsilon
+        pub fn product(&self) -> Value {
+            let parse = |bits| Value::from_bits(bits).unwrap();
+            &parse(&self.gamma) * &parse(&self.epsilon)
         }
     }
 
-    pub fn calculate_rates(report: &[String]) -> Rates {
-        fn gamma(counts: &[i32], max: usize) -> String {
-            let half = i32::try_from(max / 2).unwrap();
-            counts
+    impl Rates {
+        /// Gamma picks a bit per column with `criteria`, given how many of
+        /// `entries` have a 1 there; epsilon is its complement
+        pub fn from_column_counts(
+            counts: &[usize],
+            entries: usize,
+            criteria: &BitCriteria,
+        ) -> Result<Rates, CriteriaError> {
+            let gamma = counts
                 .iter()
-                .map(|total| if *total > half { '1' } else { '0' })
-                .collect::<String>()
+                .enumerate()
+                .map(|(column, ones)| {
+                    let one = criteria.choose(column + 1, entries - ones, *ones)?;
+                    Ok(if one { '1' } else { '0' })
+                })
+                .collect::<Result<String, CriteriaError>>()?;
+            let epsilon = gamma
+                .chars()
+                .map(|c| if c == '1' { '0' } else { '1' })
+                .collect::<String>();
+            Ok(Rates { gamma, epsilon })
         }
-        assert!(report.len() > 0, "need at least one report entry");
+    }
+
+    /// Gamma is the most common bit in each column, and 0 on a tie
+    pub fn calculate_rates(report: &[String]) -> Rates {
+        calculate_rates_with(report, &BitCriteria::most_common(Tie::PreferZero))
+            .expect("ties go to 0")
+    }
+
+    pub fn calculate_rates_with(
+        report: &[String],
+        criteria: &BitCriteria,
+    ) -> Result<Rates, CriteriaError> {
+        assert!(!report.is_empty(), "need at least one report entry");
         let counts = vec![0; report[0].len()];
         let counts = report
             .iter()
-            .fold(counts, |counts, entry| add_entry(counts, entry));
-
-        let gamma = gamma(&counts, report.len());
-        let epsilon = gamma
-            .chars()
-            .map(|c| if c == '1' { '0' } else { '1' })
-            .collect::<String>();
-
-        Rates {
-            gamma: gamma,
-            epsilon: epsilon,
-        }
+            .fold(counts, |counts, entry| add_entry(counts, entry))
+            .iter()
+            .map(|count| usize::try_from(*count).unwrap())
+            .collect::<Vec<usize>>();
+        Rates::from_column_counts(&counts, report.len(), criteria)
     }
 
     pub fn add_entry(counts: Vec<i32>, entry: &str) -> Vec<i32> {
@@ -57,8 +368,190 @@ pub mod part1 {
     }
 }
 
+/// Part 1 for large reports: entries packed into u64 words
+///
+/// Each entry is parsed once, 64 columns to a word with the first column in
+/// the top bit. Column counts are kept bit-sliced: counter plane `k` holds
+/// bit `k` of every column's count, so adding an entry is a ripple-carry add
+/// across whole words instead of one add per column.
+pub mod packed {
+    use super::criteria::{BitCriteria, CriteriaError, Tie};
+    use super::part1::Rates;
+    use std::error::Error;
+    use std::fmt;
+
+    #[derive(Debug, PartialEq)]
+    pub struct PackedReport {
+        width: usize,
+        entries: usize,
+        /// `words_per_entry` words for each entry in turn
+        words: Vec<u64>,
+    }
+
+    impl PackedReport {
+        /// Pack a report of binary strings, all as wide as the first
+        pub fn new(report: &[String]) -> Result<PackedReport, ReportError> {
+            let width = report.first().ok_or(ReportError::Empty)?.len();
+            let mut words = Vec::with_capacity(report.len() * words_per_entry(width));
+            for (index, entry) in report.iter().enumerate() {
+                if entry.len() != width {
+                    return Err(ReportError::Width {
+                        entry: index,
+                        expected: width,
+                        found: entry.len(),
+                    });
+                }
+                if let Some(column) = entry.bytes().position(|b| b != b'0' && b != b'1') {
+                    return Err(ReportError::NotBinary {
+                        entry: index,
+                        column: column + 1,
+                    });
+                }
+                words.extend(entry.as_bytes().chunks(64).map(|chunk| {
+                    let word = chunk
+                        .iter()
+                        .fold(0, |word, bit| word << 1 | u64::from(*bit == b'1'));
+                    word << (64 - chunk.len())
+                }));
+            }
+            Ok(PackedReport {
+                width,
+                entries: report.len(),
+                words,
+            })
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn len(&self) -> usize {
+            self.entries
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.entries == 0
+        }
+
+        /// Each entry's bits in report order, first column in the top bit of
+        /// the first word, and any bits past the width zero
+        pub fn entry_words(&self) -> impl Iterator<Item = &[u64]> + '_ {
+            let stride = words_per_entry(self.width);
+            (0..self.entries).map(move |entry| &self.words[entry * stride..][..stride])
+        }
+
+        /// The entries as binary strings again, in report order
+        pub fn entries(&self) -> impl Iterator<Item = String> + '_ {
+            self.entry_words().map(move |words| {
+                (0..self.width)
+                    .map(|column| {
+                        let bit = words[column / 64] >> (63 - column % 64) & 1;
+                        if bit == 1 {
+                            '1'
+                        } else {
+                            '0'
+                        }
+                    })
+                    .collect()
+            })
+        }
+
+        /// How many entries have a 1 in each column, first column first
+        pub fn column_counts(&self) -> Vec<usize> {
+            let stride = words_per_entry(self.width);
+            let mut counts = Vec::with_capacity(self.width);
+            for word in 0..stride {
+                // a count of at most usize::MAX entries needs no more planes
+                let mut planes = [0u64; usize::BITS as usize];
+                for entry in self.words[word..].iter().step_by(stride) {
+                    let mut carry = *entry;
+                    let mut plane = 0;
+                    while carry != 0 {
+                        let overflow = planes[plane] & carry;
+                        planes[plane] ^= carry;
+                        carry = overflow;
+                        plane += 1;
+                    }
+                }
+
+                let columns = (self.width - word * 64).min(64);
+                counts.extend((0..columns).map(|column| {
+                    let shift = 63 - column;
+                    planes
+                        .iter()
+                        .enumerate()
+                        .map(|(k, plane)| usize::from((plane >> shift) & 1 == 1) << k)
+                        .sum::<usize>()
+                }));
+            }
+            counts
+        }
+
+        /// The same rates as `part1::calculate_rates`
+        pub fn rates(&self) -> Rates {
+            self.rates_with(&BitCriteria::most_common(Tie::PreferZero))
+                .expect("ties go to 0")
+        }
+
+        /// The same rates as `part1::calculate_rates_with`
+        pub fn rates_with(&self, criteria: &BitCriteria) -> Result<Rates, CriteriaError> {
+            Rates::from_column_counts(&self.column_counts(), self.entries, criteria)
+        }
+    }
+
+    fn words_per_entry(width: usize) -> usize {
+        width.div_ceil(64)
+    }
+
+    /// Why a report couldn't be packed
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ReportError {
+        Empty,
+        /// `entry` is an index into the report, `column` is 1-based
+        NotBinary {
+            entry: usize,
+            column: usize,
+        },
+        Width {
+            entry: usize,
+            expected: usize,
+            found: usize,
+        },
+    }
+
+    impl fmt::Display for ReportError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                ReportError::Empty => write!(f, "need at least one report entry"),
+                ReportError::NotBinary { entry, column } => write!(
+                    f,
+                    "entry {} has a character other than 0 or 1 in column {}",
+                    entry, column
+                ),
+                ReportError::Width {
+                    entry,
+                    expected,
+                    found,
+                } => write!(
+                    f,
+                    "entry {} has {} bits, expected {}",
+                    entry, found, expected
+                ),
+            }
+        }
+    }
+
+    impl Error for ReportError {}
+}
+
 pub mod part2 {
+    use super::criteria::{BitCriteria, CriteriaError, Tie};
+    use super::value::Value;
+    use std::cmp::{Ordering, Reverse};
+    use std::collections::BinaryHeap;
 
+    /// A binary trie over bit strings, counting how many stored entries
+    /// pass through each node. Entries may repeat and may differ in length.
     #[derive(Debug, PartialEq, Default)]
     pub struct BitNode {
         pub count: i32,
@@ -72,7 +565,7 @@ pub mod part2 {
 
     impl BitNode {
         pub fn get(&self, bit: usize) -> &BitNode {
-            if let None = self.nodes[bit] {
+            if self.nodes[bit].is_none() {
                 &EMPTY_NODE
             } else {
                 self.nodes[bit].as_deref().unwrap()
@@ -82,101 +575,560 @@ pub mod part2 {
         fn get_mut(&mut self, bit: usize) -> &mut BitNode {
             self.nodes[bit].get_or_insert_with(|| Box::new(BitNode::default()))
         }
-    }
 
-    fn add_entry_to_node_tree(root: &mut BitNode, entry: &str) {
-        let mut node = root;
-        node.count += 1;
-        for ch in entry.chars() {
-            let bit = usize::try_from(ch.to_digit(2).unwrap()).unwrap();
-            node = node.get_mut(bit);
+        /// The node reached by following `prefix` from here
+        fn find(&self, prefix: &str) -> &BitNode {
+            bits(prefix).fold(self, |node, bit| node.get(bit))
+        }
+
+        /// Entries that end exactly at this node
+        fn ends(&self) -> i32 {
+            self.count - self.get(0).count - self.get(1).count
+        }
+
+        /// Store one more copy of `entry`
+        ///
+        /// Panics if `entry` has characters other than 0 and 1, as do the
+        /// other methods taking bit strings.
+        pub fn insert(&mut self, entry: &str) {
+            let mut node = self;
             node.count += 1;
+            for bit in bits(entry) {
+                node = node.get_mut(bit);
+                node.count += 1;
+            }
+        }
+
+        /// Remove one copy of `entry`, returning false if there wasn't one.
+        /// Nodes no longer on the path of any entry are dropped.
+        pub fn remove(&mut self, entry: &str) -> bool {
+            if self.count_of(entry) == 0 {
+                return false;
+            }
+            let mut node = self;
+            node.count -= 1;
+            for bit in bits(entry) {
+                if node.get(bit).count == 1 {
+                    node.nodes[bit] = None;
+                    return true;
+                }
+                node = node.nodes[bit].as_deref_mut().unwrap();
+                node.count -= 1;
+            }
+            true
+        }
+
+        /// How many copies of exactly `entry` are stored
+        pub fn count_of(&self, entry: &str) -> i32 {
+            self.find(entry).ends()
+        }
+
+        /// How many stored entries start with `prefix`, counting repeats
+        pub fn count_with_prefix(&self, prefix: &str) -> i32 {
+            self.find(prefix).count
+        }
+
+        /// The stored entries in order, shorter entries before the longer
+        /// ones they prefix, repeated entries once per copy
+        pub fn iter(&self) -> Entries<'_> {
+            Entries {
+                stack: vec![(self, String::new())],
+                pending: None,
+            }
+        }
+
+        /// Add every entry of `other` to this trie
+        pub fn merge(&mut self, other: &BitNode) {
+            self.count += other.count;
+            for (bit, theirs) in other.nodes.iter().enumerate() {
+                if let Some(theirs) = theirs {
+                    self.get_mut(bit).merge(theirs);
+                }
+            }
+        }
+
+        /// The entries of this trie less those of `other`, copy for copy
+        pub fn difference(&self, other: &BitNode) -> BitNode {
+            let mut result = BitNode {
+                count: (self.ends() - other.ends()).max(0),
+                nodes: [None, None],
+            };
+            for (bit, ours) in self.nodes.iter().enumerate() {
+                if let Some(ours) = ours {
+                    let child = ours.difference(other.get(bit));
+                    if child.count > 0 {
+                        result.count += child.count;
+                        result.nodes[bit] = Some(Box::new(child));
+                    }
+                }
+            }
+            result
+        }
+
+        /// The `k` stored entries closest to `query` by Hamming distance,
+        /// nearest first and in order among equals, repeated entries once
+        /// per copy. Only entries as long as `query` are compared.
+        ///
+        /// Partial matches are extended closest first, skipping branches with
+        /// no entries left, and the search stops once `k` entries are found.
+        pub fn nearest(&self, query: &str, k: usize) -> Vec<Match> {
+            let query = bits(query).collect::<Vec<usize>>();
+            let mut matches = Vec::new();
+            let mut frontier = BinaryHeap::from([Reverse(Candidate {
+                distance: 0,
+                path: String::new(),
+                node: self,
+            })]);
+
+            while let Some(Reverse(candidate)) = frontier.pop() {
+                if matches.len() >= k {
+                    break;
+                }
+                let depth = candidate.path.len();
+                if depth == query.len() {
+                    let copies = usize::try_from(candidate.node.ends()).unwrap();
+                    let take = copies.min(k - matches.len());
+                    matches.extend((0..take).map(|_| Match {
+                        entry: candidate.path.clone(),
+                        distance: candidate.distance,
+                    }));
+                    continue;
+                }
+                for (bit, child) in candidate.node.nodes.iter().enumerate() {
+                    if let Some(child) = child.as_deref().filter(|child| child.count > 0) {
+                        let mut path = candidate.path.clone();
+                        path.push(if bit == 1 { '1' } else { '0' });
+                        frontier.push(Reverse(Candidate {
+                            distance: candidate.distance + usize::from(bit != query[depth]),
+                            path,
+                            node: child,
+                        }));
+                    }
+                }
+            }
+            matches
         }
+
+        /// Every stored entry within Hamming distance `distance` of `query`,
+        /// nearest first and in order among equals. Only entries as long as
+        /// `query` are compared, and branches that have already strayed too
+        /// far are not followed.
+        pub fn within(&self, query: &str, distance: usize) -> Vec<Match> {
+            fn visit(
+                node: &BitNode,
+                query: &[usize],
+                path: &mut String,
+                strayed: usize,
+                limit: usize,
+                matches: &mut Vec<Match>,
+            ) {
+                let Some((bit, rest)) = query.split_first() else {
+                    let copies = usize::try_from(node.ends()).unwrap();
+                    matches.extend((0..copies).map(|_| Match {
+                        entry: path.clone(),
+                        distance: strayed,
+                    }));
+                    return;
+                };
+                for (child_bit, child) in node.nodes.iter().enumerate() {
+                    let strayed = strayed + usize::from(child_bit != *bit);
+                    if let Some(child) = child.as_deref() {
+                        if child.count > 0 && strayed <= limit {
+                            path.push(if child_bit == 1 { '1' } else { '0' });
+                            visit(child, rest, path, strayed, limit, matches);
+                            path.pop();
+                        }
+                    }
+                }
+            }
+
+            let query = bits(query).collect::<Vec<usize>>();
+            let mut matches = Vec::new();
+            visit(self, &query, &mut String::new(), 0, distance, &mut matches);
+            matches.sort_by_key(|m| m.distance);
+            matches
+        }
+    }
+
+    /// A stored entry found by a distance search
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Match {
+        pub entry: String,
+        /// Bits that differ from the query
+        pub distance: usize,
     }
 
-    fn calculate_rating<'a>(
-        bit_tree: &'a BitNode,
+    /// A partial match waiting to be extended, ordered closest first and
+    /// then by path, so full matches come out in the same order
+    struct Candidate<'a> {
+        distance: usize,
+        path: String,
+        node: &'a BitNode,
+    }
+
+    impl PartialEq for Candidate<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            (self.distance, &self.path) == (other.distance, &other.path)
+        }
+    }
+
+    impl Eq for Candidate<'_> {}
+
+    impl PartialOrd for Candidate<'_> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Candidate<'_> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            (self.distance, &self.path).cmp(&(other.distance, &other.path))
+        }
+    }
+
+    fn bits(entry: &str) -> impl Iterator<Item = usize> + '_ {
+        entry.chars().map(|ch| match ch {
+            '0' => 0,
+            '1' => 1,
+            _ => panic!("not a binary digit: {:?}", ch),
+        })
+    }
+
+    pub struct Entries<'a> {
+        /// Nodes still to visit, with the bits leading to them
+        stack: Vec<(&'a BitNode, String)>,
+        /// An entry still to yield, and how many more copies of it
+        pending: Option<(String, i32)>,
+    }
+
+    impl<'a> Iterator for Entries<'a> {
+        type Item = String;
+
+        fn next(&mut self) -> Option<String> {
+            loop {
+                if let Some((entry, copies)) = &mut self.pending {
+                    if *copies > 0 {
+                        *copies -= 1;
+                        return Some(entry.clone());
+                    }
+                    self.pending = None;
+                }
+
+                let (node, path) = self.stack.pop()?;
+                for (bit, child) in node.nodes.iter().enumerate().rev() {
+                    if let Some(child) = child {
+                        let mut path = path.clone();
+                        path.push(if bit == 1 { '1' } else { '0' });
+                        self.stack.push((child, path));
+                    }
+                }
+                if node.ends() > 0 {
+                    self.pending = Some((path, node.ends()));
+                }
+            }
+        }
+    }
+
+    impl<'a> IntoIterator for &'a BitNode {
+        type Item = String;
+        type IntoIter = Entries<'a>;
+
+        fn into_iter(self) -> Entries<'a> {
+            self.iter()
+        }
+    }
+
+    impl<S: AsRef<str>> FromIterator<S> for BitNode {
+        fn from_iter<I: IntoIterator<Item = S>>(entries: I) -> Self {
+            let mut result = BitNode::default();
+            result.extend(entries);
+            result
+        }
+    }
+
+    impl<S: AsRef<str>> Extend<S> for BitNode {
+        fn extend<I: IntoIterator<Item = S>>(&mut self, entries: I) {
+            for entry in entries {
+                self.insert(entry.as_ref());
+            }
+        }
+    }
+
+    /// A counted binary trie the ratings can walk, boxed or arena-backed
+    pub trait BitTree {
+        type Node<'a>: Copy
+        where
+            Self: 'a;
+
+        fn root(&self) -> Self::Node<'_>;
+
+        /// The child of `node` for `bit`, or an empty node with count 0
+        fn child<'a>(&'a self, node: Self::Node<'a>, bit: usize) -> Self::Node<'a>;
+
+        fn count<'a>(&'a self, node: Self::Node<'a>) -> i32;
+    }
+
+    impl BitTree for BitNode {
+        type Node<'a> = &'a BitNode;
+
+        fn root(&self) -> &BitNode {
+            self
+        }
+
+        fn child<'a>(&'a self, node: &'a BitNode, bit: usize) -> &'a BitNode {
+            node.get(bit)
+        }
+
+        fn count<'a>(&'a self, node: &'a BitNode) -> i32 {
+            node.count
+        }
+    }
+
+    /// Follow the bits `criteria` picks from the counts below each child,
+    /// or the only entry left once there is just one
+    pub fn rating_with<T: BitTree + ?Sized>(
+        bit_tree: &T,
         num_bits: usize,
-        choose_child: &dyn Fn(&(&'a BitNode, &'a BitNode)) -> (char, &'a BitNode),
-    ) -> String {
+        criteria: &BitCriteria,
+    ) -> Result<String, CriteriaError> {
         let mut result: Vec<char> = Vec::new();
-        let mut node = bit_tree;
-        for _ in 0..num_bits {
-            let child: (char, &BitNode);
-            let nodes = (node.get(0), node.get(1));
-
-            child = if nodes.0.count + nodes.1.count == 1 {
-                // only one node so choose it!
-                if nodes.0.count == 1 {
-                    ('0', nodes.0)
-                } else {
-                    ('1', nodes.1)
+        let mut node = bit_tree.root();
+        for column in 1..=num_bits {
+            let nodes = (bit_tree.child(node, 0), bit_tree.child(node, 1));
+            let counts = (bit_tree.count(nodes.0), bit_tree.count(nodes.1));
+
+            // once a branch is empty the only entries left are down the other
+            let one = match counts {
+                (0, ones) if ones > 0 => true,
+                (zeros, 0) if zeros > 0 => false,
+                _ => {
+                    let count = |count| usize::try_from(count).unwrap();
+                    criteria.choose(column, count(counts.0), count(counts.1))?
                 }
-            } else {
-                choose_child(&nodes)
             };
-            result.push(child.0);
-            node = child.1;
+            result.push(if one { '1' } else { '0' });
+            node = if one { nodes.1 } else { nodes.0 };
         }
-        String::from_iter(result)
+        Ok(String::from_iter(result))
     }
 
-    pub fn o2_generator_rating(bit_tree: &BitNode, num_bits: usize) -> String {
-        calculate_rating(bit_tree, num_bits, &choose_o2_generator)
+    /// Most common bits, 1 on a tie
+    pub fn o2_generator_rating<T: BitTree + ?Sized>(bit_tree: &T, num_bits: usize) -> String {
+        rating_with(
+            bit_tree,
+            num_bits,
+            &BitCriteria::most_common(Tie::PreferOne),
+        )
+        .expect("ties go to 1")
     }
 
-    fn choose_o2_generator<'a>(nodes: &(&'a BitNode, &'a BitNode)) -> (char, &'a BitNode) {
-        if nodes.1.count >= nodes.0.count {
-            ('1', nodes.1)
-        } else {
-            ('0', nodes.0)
+    /// Least common bits, 0 on a tie
+    pub fn co2_scrubber_rating<T: BitTree + ?Sized>(bit_tree: &T, num_bits: usize) -> String {
+        rating_with(
+            bit_tree,
+            num_bits,
+            &BitCriteria::least_common(Tie::PreferZero),
+        )
+        .expect("ties go to 0")
+    }
+
+    pub fn life_support_rating<T: BitTree + ?Sized>(bit_tree: &T, num_bits: usize) -> Value {
+        let co2_scrubber = Value::from_bits(&co2_scrubber_rating(bit_tree, num_bits)).unwrap();
+        let o2_generator = Value::from_bits(&o2_generator_rating(bit_tree, num_bits)).unwrap();
+        &co2_scrubber * &o2_generator
+    }
+
+    /// BitNode::from(Vec["list", "of", "entries"])
+    impl From<&Vec<String>> for BitNode {
+        fn from(vec: &Vec<String>) -> Self {
+            vec.iter().collect()
         }
     }
+}
 
-    pub fn co2_scrubber_rating(bit_tree: &BitNode, num_bits: usize) -> String {
-        calculate_rating(bit_tree, num_bits, &choose_co2_scrubber)
+/// Part 2 for large reports: the counted trie in one `Vec`
+///
+/// Nodes refer to their children by index instead of by `Box`, so building
+/// the trie grows a single allocation and walking it stays within one block
+/// of memory. Index 0 is an empty node standing in for every missing child.
+pub mod arena {
+    use super::packed::PackedReport;
+    use super::part2::BitTree;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    struct Node {
+        count: i32,
+        children: [u32; 2],
+    }
+
+    const EMPTY: u32 = 0;
+    const ROOT: u32 = 1;
+
+    #[derive(Debug, PartialEq)]
+    pub struct ArenaTrie {
+        nodes: Vec<Node>,
     }
 
-    fn choose_co2_scrubber<'a>(nodes: &(&'a BitNode, &'a BitNode)) -> (char, &'a BitNode) {
-        if nodes.0.count <= nodes.1.count {
-            ('0', nodes.0)
-        } else {
-            ('1', nodes.1)
+    impl ArenaTrie {
+        pub fn new() -> Self {
+            ArenaTrie {
+                nodes: vec![Node::default(); 2],
+            }
+        }
+
+        /// Store one more copy of `entry`, which must be a binary string
+        pub fn insert(&mut self, entry: &str) {
+            self.insert_path(entry.chars().map(|ch| match ch {
+                '0' => 0,
+                '1' => 1,
+                _ => panic!("not a binary digit: {:?}", ch),
+            }));
+        }
+
+        /// Store one more entry of `width` bits, packed as in
+        /// `PackedReport::entry_words`: first bit in the top of `words[0]`
+        pub fn insert_bits(&mut self, words: &[u64], width: usize) {
+            self.insert_path(
+                (0..width).map(|column| (words[column / 64] >> (63 - column % 64) & 1) as usize),
+            );
+        }
+
+        fn insert_path(&mut self, bits: impl Iterator<Item = usize>) {
+            let mut node = ROOT;
+            self.nodes[node as usize].count += 1;
+            for bit in bits {
+                let mut child = self.nodes[node as usize].children[bit];
+                if child == EMPTY {
+                    child = u32::try_from(self.nodes.len()).expect("too many trie nodes");
+                    self.nodes.push(Node::default());
+                    self.nodes[node as usize].children[bit] = child;
+                }
+                node = child;
+                self.nodes[node as usize].count += 1;
+            }
+        }
+
+        /// Nodes in use, not counting the shared empty node
+        pub fn node_count(&self) -> usize {
+            self.nodes.len() - 1
         }
     }
 
-    pub fn life_support_rating(bit_tree: &BitNode, num_bits: usize) -> i32 {
-        let co2_scrubber =
-            i32::from_str_radix(&co2_scrubber_rating(bit_tree, num_bits), 2).unwrap();
-        let o2_generator =
-            i32::from_str_radix(&o2_generator_rating(bit_tree, num_bits), 2).unwrap();
-        co2_scrubber * o2_generator
+    impl Default for ArenaTrie {
+        fn default() -> Self {
+            ArenaTrie::new()
+        }
     }
 
-    /// BitNode::from(Vec["list", "of", "entries"])
-    impl From<&Vec<String>> for BitNode {
+    impl BitTree for ArenaTrie {
+        type Node<'a> = u32;
+
+        fn root(&self) -> u32 {
+            ROOT
+        }
+
+        fn child(&self, node: u32, bit: usize) -> u32 {
+            self.nodes[node as usize].children[bit]
+        }
+
+        fn count(&self, node: u32) -> i32 {
+            self.nodes[node as usize].count
+        }
+    }
+
+    impl<S: AsRef<str>> FromIterator<S> for ArenaTrie {
+        fn from_iter<I: IntoIterator<Item = S>>(entries: I) -> Self {
+            let mut result = ArenaTrie::new();
+            for entry in entries {
+                result.insert(entry.as_ref());
+            }
+            result
+        }
+    }
+
+    impl From<&Vec<String>> for ArenaTrie {
         fn from(vec: &Vec<String>) -> Self {
-            let mut result = BitNode::default();
-            for entry in vec {
-                add_entry_to_node_tree(&mut result, entry.as_str());
+            vec.iter().collect()
+        }
+    }
+
+    /// Straight from the packed bits, without a string per entry
+    impl From<&PackedReport> for ArenaTrie {
+        fn from(report: &PackedReport) -> Self {
+            let mut result = ArenaTrie::new();
+            for words in report.entry_words() {
+                result.insert_bits(words, report.width());
             }
             result
         }
     }
 }
 
+/// Only the packed report is kept: part 2 builds a trie from its bits
+pub struct Day3 {
+    packed: packed::PackedReport,
+}
+
+impl Solution for Day3 {
+    fn parse(input: &[String]) -> ParseResult<Self> {
+        let first = parse::first_line(DAY, input)?;
+        if first.text.is_empty() {
+            return Err(first.missing("report entry"));
+        }
+        let packed = packed::PackedReport::new(input).map_err(|err| {
+            let line = |entry: usize| Line::new(DAY, entry + 1, &input[entry]);
+            match err {
+                ReportError::Empty => first.missing("report entry"),
+                ReportError::NotBinary { entry, column } => {
+                    // every byte before the column is a 0 or 1
+                    let line = line(entry);
+                    let rest = &line.text[column - 1..];
+                    let bad = &rest[..rest.chars().next().map_or(0, char::len_utf8)];
+                    line.error(bad, "expected a binary digit")
+                }
+                ReportError::Width {
+                    entry,
+                    expected,
+                    found,
+                } => {
+                    let line = line(entry);
+                    line.error(
+                        line.text,
+                        format!("expected {} bits, found {}", expected, found),
+                    )
+                }
+            }
+        })?;
+        Ok(Day3 { packed })
+    }
+
+    fn part1(&self) -> SolveResult {
+        Ok(self.packed.rates().product().into())
+    }
+
+    fn part2(&self) -> Option<SolveResult> {
+        let nodes = arena::ArenaTrie::from(&self.packed);
+        Some(Ok(
+            part2::life_support_rating(&nodes, self.packed.width()).into()
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::arena::ArenaTrie;
+    use super::criteria::{BitCriteria, CriteriaError, Tie};
+    use super::packed::{PackedReport, ReportError};
     use super::part1::*;
     use super::part2::*;
+    use super::value::Value;
+    use super::{Day3, DAY};
+    use crate::examples;
+    use crate::solution::{Answer, Solution};
 
     fn report() -> Vec<String> {
-        [
-            "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000",
-            "11001", "00010", "01010",
-        ]
-        .map(|s| s.to_string())
-        .to_vec()
+        let (input, _) = examples::load(&examples::default_fixtures_dir(), DAY).unwrap();
+        input
     }
 
     #[test]
@@ -198,7 +1150,7 @@ mod tests {
                 epsilon: String::from("01001"),
             }
         );
-        assert_eq!(rates.product(), 198)
+        assert_eq!(rates.product(), Value::from(198u64))
     }
 
     #[test]
@@ -210,7 +1162,7 @@ mod tests {
 
     #[test]
     fn builds_bit_tree() {
-        let entries = ["10", "11", "01"].map(|s| String::from(s)).to_vec();
+        let entries = ["10", "11", "01"].map(String::from).to_vec();
         let bit_tree = BitNode::from(&entries);
 
         assert_eq!(3, bit_tree.count);
@@ -243,6 +1195,394 @@ mod tests {
     #[test]
     fn calculates_life_support_rating() {
         let bit_tree = BitNode::from(&report());
-        assert_eq!(life_support_rating(&bit_tree, report()[0].len()), 230);
+        assert_eq!(
+            life_support_rating(&bit_tree, report()[0].len()),
+            Value::from(230u64)
+        );
+    }
+
+    #[test]
+    fn rejects_non_binary_entries() {
+        let report = ["00100", "11210"].map(String::from);
+        let err = Day3::parse(&report).err().unwrap();
+        assert_eq!((2, 3, "2"), (err.line, err.column, err.text.as_str()));
+
+        let report = ["00100", "1111"].map(String::from);
+        let err = Day3::parse(&report).err().unwrap();
+        assert_eq!((2, 1), (err.line, err.column));
+        assert_eq!("expected 5 bits, found 4", err.reason);
+
+        let report = ["000", "1é"].map(String::from);
+        let err = Day3::parse(&report).err().unwrap();
+        assert_eq!((2, 2, "é"), (err.line, err.column, err.text.as_str()));
+        assert_eq!("expected a binary digit", err.reason);
+    }
+
+    #[test]
+    fn reads_values_of_any_width() {
+        assert_eq!(Some(Value::from(22u64)), Value::from_bits("0010110"));
+        assert_eq!(Some(Value::from(0u64)), Value::from_bits("000"));
+        assert_eq!(None, Value::from_bits("102"));
+        assert_eq!(None, Value::from_bits(""));
+
+        let wide = format!("1{}", "0".repeat(100));
+        assert_eq!(
+            Some(1u128 << 100),
+            Value::from_bits(&wide).unwrap().to_u128()
+        );
+        assert_eq!(
+            "1267650600228229401496703205376",
+            Value::from_bits(&wide).unwrap().to_string()
+        );
+
+        let big = format!("1{}", "0".repeat(200));
+        assert_eq!(None, Value::from_bits(&big).unwrap().to_u128());
+        assert_eq!(
+            "1606938044258990275541962092341162602522202993782792835301376",
+            Value::from_bits(&big).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn multiplies_exactly() {
+        let max = Value::from(u64::MAX);
+        assert_eq!(
+            "340282366920938463426481119284349108225",
+            (&max * &max).to_string()
+        );
+        let max = Value::from(u128::MAX);
+        assert_eq!(
+            "115792089237316195423570985008687907852589419931798687112530834793049593217025",
+            (&max * &max).to_string()
+        );
+        let ones = Value::from_bits(&"1".repeat(128)).unwrap();
+        assert_eq!(&max * &max, &ones * &ones);
+        assert_eq!(Some(0), (&max * &Value::from(0u64)).to_u64());
+    }
+
+    #[test]
+    fn rates_wide_reports_exactly() {
+        let report = [
+            format!("1{}", "0".repeat(69)),
+            format!("1{}1", "0".repeat(68)),
+            format!("0{}", "1".repeat(69)),
+        ]
+        .to_vec();
+        let rates = calculate_rates(&report);
+        assert_eq!(format!("1{}1", "0".repeat(68)), rates.gamma);
+        // (2^69 + 1) * (2^69 - 2)
+        assert_eq!(
+            "348449143727040986585905302199771942879230",
+            rates.product().to_string()
+        );
+
+        let day3 = Day3::parse(&report).unwrap();
+        assert_eq!(
+            Ok(Answer::Text(
+                "348449143727040986585905302199771942879230".to_string()
+            )),
+            day3.part1()
+        );
+        // (2^69 + 1) * (2^69 - 1)
+        assert_eq!(
+            Some(Ok(Answer::Text(
+                "348449143727040986586495598010130648530943".to_string()
+            ))),
+            day3.part2()
+        );
+    }
+
+    #[test]
+    fn packed_counts_match_column_sums() {
+        let packed = PackedReport::new(&report()).unwrap();
+        assert_eq!((12, 5), (packed.len(), packed.width()));
+        assert_eq!(vec![7, 5, 8, 7, 5], packed.column_counts());
+        assert_eq!(calculate_rates(&report()), packed.rates());
+    }
+
+    #[test]
+    fn packed_rates_match_across_word_boundaries() {
+        // a cheap deterministic scramble, so every column gets a different count
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for width in [1, 63, 64, 65, 130] {
+            let report = (0..301)
+                .map(|_| {
+                    (0..width)
+                        .map(|_| {
+                            state ^= state << 13;
+                            state ^= state >> 7;
+                            state ^= state << 17;
+                            if state % 3 == 0 {
+                                '1'
+                            } else {
+                                '0'
+                            }
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<String>>();
+            let packed = PackedReport::new(&report).unwrap();
+            let counts = report
+                .iter()
+                .fold(vec![0; width], |counts, entry| add_entry(counts, entry));
+            let counts = counts.iter().map(|c| *c as usize).collect::<Vec<usize>>();
+            assert_eq!(counts, packed.column_counts(), "width {}", width);
+            assert_eq!(calculate_rates(&report), packed.rates(), "width {}", width);
+            assert!(
+                packed.entries().eq(report.iter().cloned()),
+                "width {}",
+                width
+            );
+            assert_eq!(
+                ArenaTrie::from(&report),
+                ArenaTrie::from(&packed),
+                "width {}",
+                width
+            );
+        }
+    }
+
+    #[test]
+    fn packs_only_binary_reports_of_one_width() {
+        assert_eq!(Err(ReportError::Empty), PackedReport::new(&[]));
+        let uneven = ["101", "10"].map(String::from);
+        assert_eq!(
+            Err(ReportError::Width {
+                entry: 1,
+                expected: 3,
+                found: 2
+            }),
+            PackedReport::new(&uneven)
+        );
+        let err = PackedReport::new(&["101", "1x1"].map(String::from)).unwrap_err();
+        assert_eq!(
+            ReportError::NotBinary {
+                entry: 1,
+                column: 2
+            },
+            err
+        );
+        assert_eq!(
+            "entry 1 has a character other than 0 or 1 in column 2",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn inserts_and_removes_entries() {
+        let mut trie = BitNode::from(&report());
+        assert_eq!(7, trie.count_with_prefix("1"));
+        assert_eq!(3, trie.count_with_prefix("101"));
+        assert_eq!(12, trie.count_with_prefix(""));
+        assert_eq!(1, trie.count_of("10110"));
+        assert_eq!(0, trie.count_of("1011"));
+
+        trie.insert("10110");
+        trie.insert("1011");
+        assert_eq!((2, 1), (trie.count_of("10110"), trie.count_of("1011")));
+        assert_eq!(5, trie.count_with_prefix("101"));
+
+        assert!(trie.remove("1011"));
+        assert!(trie.remove("10110"));
+        assert!(!trie.remove("1011"));
+        assert!(!trie.remove("11111"));
+        assert_eq!(BitNode::from(&report()), trie);
+
+        for entry in report() {
+            assert!(trie.remove(&entry));
+        }
+        assert_eq!(BitNode::default(), trie);
+    }
+
+    #[test]
+    fn iterates_entries_in_order() {
+        let trie = ["10", "1", "0110", "10", "", "01"]
+            .iter()
+            .collect::<BitNode>();
+        assert_eq!(
+            vec!["", "01", "0110", "1", "10", "10"],
+            trie.iter().collect::<Vec<String>>()
+        );
+
+        let mut sorted = report();
+        sorted.sort();
+        assert_eq!(sorted, BitNode::from(&report()).iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn merges_and_differences_tries() {
+        let mut trie = ["00", "01", "01", "1"].iter().collect::<BitNode>();
+        let other = ["01", "1", "11"].iter().collect::<BitNode>();
+
+        let difference = trie.difference(&other);
+        assert_eq!(vec!["00", "01"], difference.iter().collect::<Vec<_>>());
+        assert_eq!(["00", "01"].iter().collect::<BitNode>(), difference);
+        assert_eq!(
+            vec!["11"],
+            other.difference(&trie).iter().collect::<Vec<_>>()
+        );
+
+        trie.merge(&other);
+        assert_eq!(
+            vec!["00", "01", "01", "01", "1", "1", "11"],
+            trie.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(7, trie.count);
+        assert_eq!(BitNode::default(), trie.difference(&trie));
+    }
+
+    #[test]
+    fn arena_trie_rates_like_boxed_nodes() {
+        let arena = ArenaTrie::from(&report());
+        assert_eq!(o2_generator_rating(&arena, 5), "10111");
+        assert_eq!(co2_scrubber_rating(&arena, 5), "01010");
+        assert_eq!(Value::from(230u64), life_support_rating(&arena, 5));
+
+        let entries = ["10", "11", "01"].map(String::from).to_vec();
+        let arena = ArenaTrie::from(&entries);
+        let boxed = BitNode::from(&entries);
+        assert_eq!(6, arena.node_count());
+        assert_eq!(boxed.count, arena.count(arena.root()));
+        let one = arena.child(arena.root(), 1);
+        assert_eq!(boxed.get(1).get(0).count, arena.count(arena.child(one, 0)));
+        assert_eq!(0, arena.count(arena.child(arena.child(one, 0), 1)));
+        assert_eq!(ArenaTrie::new(), ArenaTrie::from(&vec![]));
+    }
+
+    #[test]
+    fn rates_with_tie_policies() {
+        // columns with 2, 1 and 3 ones out of 4
+        let report = ["101", "001", "111", "000"].map(String::from).to_vec();
+        assert_eq!("001", calculate_rates(&report).gamma);
+
+        let prefer_one = BitCriteria::most_common(Tie::PreferOne);
+        let rates = calculate_rates_with(&report, &prefer_one).unwrap();
+        assert_eq!(("101", "010"), (&rates.gamma[..], &rates.epsilon[..]));
+        let packed = PackedReport::new(&report).unwrap();
+        assert_eq!(rates, packed.rates_with(&prefer_one).unwrap());
+
+        let least = BitCriteria::least_common(Tie::PreferOne);
+        assert_eq!("110", calculate_rates_with(&report, &least).unwrap().gamma);
+
+        let reject = BitCriteria::most_common(Tie::Reject);
+        let err = calculate_rates_with(&report, &reject).unwrap_err();
+        assert_eq!(
+            CriteriaError {
+                column: 1,
+                zeros: 2,
+                ones: 2
+            },
+            err
+        );
+        assert_eq!(
+            "no bit chosen for column 1 with 2 zeros and 2 ones",
+            err.to_string()
+        );
+        assert_eq!(Err(err), packed.rates_with(&reject));
+    }
+
+    #[test]
+    fn rates_with_custom_criteria() {
+        let report = report();
+        // a 1 wherever at least a third of the entries have one
+        let third = BitCriteria::custom(|zeros, ones| Some(ones * 2 >= zeros));
+        assert_eq!(
+            "11111",
+            calculate_rates_with(&report, &third).unwrap().gamma
+        );
+
+        let bit_tree = BitNode::from(&report);
+        let arena = ArenaTrie::from(&report);
+        let o2 = BitCriteria::most_common(Tie::PreferOne);
+        assert_eq!(
+            o2_generator_rating(&bit_tree, 5),
+            rating_with(&arena, 5, &o2).unwrap()
+        );
+        // the example's oxygen rating only narrows on a tie at column 5
+        let err = rating_with(&bit_tree, 5, &BitCriteria::most_common(Tie::Reject));
+        assert_eq!(5, err.unwrap_err().column);
+        // once one entry is left it is followed whatever the criteria
+        assert_eq!(Ok("11110".to_string()), rating_with(&bit_tree, 5, &third));
+    }
+
+    #[test]
+    fn ratings_follow_the_only_branch_left() {
+        let twice = ["11", "11"].map(String::from).to_vec();
+        let bit_tree = BitNode::from(&twice);
+        assert_eq!("11", co2_scrubber_rating(&bit_tree, 2));
+        assert_eq!("11", co2_scrubber_rating(&ArenaTrie::from(&twice), 2));
+        let reject = BitCriteria::least_common(Tie::Reject);
+        assert_eq!(Ok("11".to_string()), rating_with(&bit_tree, 2, &reject));
+        assert_eq!("BitCriteria(LeastCommon(Reject))", format!("{:?}", reject));
+    }
+
+    /// Entries with their distances, to compare against literals
+    fn pairs(matches: Vec<Match>) -> Vec<(String, usize)> {
+        matches.into_iter().map(|m| (m.entry, m.distance)).collect()
+    }
+
+    #[test]
+    fn finds_nearest_entries() {
+        let trie = BitNode::from(&report());
+        let nearest = |query, k| pairs(trie.nearest(query, k));
+        assert_eq!(vec![("10110".to_string(), 0)], nearest("10110", 1));
+        assert_eq!(
+            vec![
+                ("10110".to_string(), 0),
+                ("10111".to_string(), 1),
+                ("11110".to_string(), 1),
+                ("00010".to_string(), 2),
+            ],
+            nearest("10110", 4)
+        );
+        assert_eq!(12, nearest("00000", 20).len());
+        assert!(nearest("101", 3).is_empty());
+        assert!(nearest("10110", 0).is_empty());
+
+        let repeated = ["0011", "0011", "0111", "1", "00111"]
+            .iter()
+            .collect::<BitNode>();
+        assert_eq!(
+            vec![("0011".to_string(), 1), ("0011".to_string(), 1)],
+            pairs(repeated.nearest("0001", 2))
+        );
+    }
+
+    #[test]
+    fn finds_entries_within_a_distance() {
+        let trie = BitNode::from(&report());
+        let within = |query, distance| pairs(trie.within(query, distance));
+        assert_eq!(vec![("10110".to_string(), 0)], within("10110", 0));
+        assert_eq!(
+            vec![
+                ("10110".to_string(), 0),
+                ("10111".to_string(), 1),
+                ("11110".to_string(), 1),
+            ],
+            within("10110", 1)
+        );
+        assert_eq!(12, within("10110", 5).len());
+
+        // both searches agree with a scan of every entry
+        for query in ["00000", "11111", "01010", "10011"] {
+            for distance in 0..=5 {
+                let mut scanned = report()
+                    .into_iter()
+                    .map(|entry| {
+                        let apart = entry.chars().zip(query.chars()).filter(|(a, b)| a != b);
+                        (apart.count(), entry)
+                    })
+                    .filter(|(apart, _)| *apart <= distance)
+                    .collect::<Vec<_>>();
+                scanned.sort();
+                let scanned = scanned
+                    .into_iter()
+                    .map(|(apart, entry)| (entry, apart))
+                    .collect::<Vec<_>>();
+                assert_eq!(scanned, within(query, distance));
+                let k = scanned.len();
+                assert_eq!(scanned, pairs(trie.nearest(query, k)));
+            }
+        }
     }
 }
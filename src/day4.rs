@@ -5,11 +5,15 @@
 /// column. As each number lands, the appropriate row and column are
 /// decremented.
 ///
+use crate::parse::{self, Line, ParseResult};
+use crate::solution::{Answer, Solution, SolveError, SolveResult};
 use std::collections::HashMap;
 
+const DAY: u32 = 4;
+
 pub const BOARD_SIZE: usize = 5;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Board {
     pub numbers: HashMap<usize, (usize, usize)>,
     pub rows: [usize; BOARD_SIZE],
@@ -17,16 +21,34 @@ pub struct Board {
 }
 
 impl Board {
-    pub fn from_strings(input: Vec<String>) -> Self {
+    pub fn from_strings(input: Vec<String>) -> ParseResult<Self> {
+        Board::from_lines(&parse::lines(DAY, &input).collect::<Vec<Line>>())
+    }
+
+    fn from_lines(lines: &[Line]) -> ParseResult<Self> {
         let mut result = Board::default();
 
-        for (row, row_str) in input.iter().enumerate() {
-            for (col, col_str) in row_str.split_whitespace().enumerate() {
-                let number = col_str.parse::<usize>().unwrap();
+        if lines.len() != BOARD_SIZE {
+            let last = lines.last().copied().unwrap_or(Line::new(DAY, 1, ""));
+            return Err(last.error(
+                last.text,
+                format!("expected {} board rows, found {}", BOARD_SIZE, lines.len()),
+            ));
+        }
+        for (row, line) in lines.iter().enumerate() {
+            let cols = line.text.split_whitespace().collect::<Vec<&str>>();
+            if cols.len() != BOARD_SIZE {
+                return Err(line.error(
+                    line.text,
+                    format!("expected {} numbers, found {}", BOARD_SIZE, cols.len()),
+                ));
+            }
+            for (col, col_str) in cols.into_iter().enumerate() {
+                let number = line.parse::<usize>(col_str)?;
                 result.numbers.insert(number, (row, col));
             }
         }
-        result
+        Ok(result)
     }
 
     pub fn play(&mut self, num: usize) -> Option<usize> {
@@ -43,28 +65,41 @@ impl Board {
     }
 }
 
+#[derive(Clone)]
 pub struct Game {
     pub boards: Vec<Board>,
     pub turns: Vec<usize>,
 }
 
 impl Game {
-    pub fn from_strings(strings: &Vec<String>) -> Self {
-        let turns = (&strings[0])
+    pub fn from_strings(strings: &[String]) -> ParseResult<Self> {
+        let first = parse::first_line(DAY, strings)?;
+        let turns = first
+            .text
             .split(',')
-            .map(|s| s.parse::<usize>().unwrap())
-            .collect::<Vec<usize>>();
+            .map(|s| first.parse::<usize>(s))
+            .collect::<ParseResult<Vec<usize>>>()?;
 
         let mut result = Game {
-            turns: turns,
+            turns,
             boards: Vec::new(),
         };
-        for board_src in strings[1..].chunks(6) {
-            result
-                .boards
-                .push(Board::from_strings(board_src[1..].to_vec()));
+        let mut lines = parse::lines(DAY, strings).skip(1).collect::<Vec<Line>>();
+        // blank lines after the last board are allowed
+        while lines.last().is_some_and(|line| line.text.trim().is_empty()) {
+            lines.pop();
         }
-        result
+        for board_src in lines.chunks(BOARD_SIZE + 1) {
+            let separator = board_src[0];
+            if !separator.text.trim().is_empty() {
+                return Err(separator.error(separator.text, "expected a blank line before board"));
+            }
+            result.boards.push(Board::from_lines(&board_src[1..])?);
+        }
+        if result.boards.is_empty() {
+            return Err(first.error(first.text, "expected boards after the drawn numbers"));
+        }
+        Ok(result)
     }
 
     pub fn play_to_win(&mut self) -> Option<usize> {
@@ -100,13 +135,44 @@ impl Game {
     }
 }
 
+pub struct Day4 {
+    game: Game,
+}
+
+impl Solution for Day4 {
+    fn parse(input: &[String]) -> ParseResult<Self> {
+        Ok(Day4 {
+            game: Game::from_strings(input)?,
+        })
+    }
+
+    fn part1(&self) -> SolveResult {
+        let score = self
+            .game
+            .clone()
+            .play_to_win()
+            .ok_or_else(|| SolveError::NoAnswer("no board ever wins".to_string()));
+        score.and_then(Answer::try_from)
+    }
+
+    fn part2(&self) -> Option<SolveResult> {
+        let score = self
+            .game
+            .clone()
+            .play_to_lose()
+            .ok_or_else(|| SolveError::NoAnswer("some board never wins".to_string()));
+        Some(score.and_then(Answer::try_from))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::examples;
 
     #[test]
     fn sets_up_a_board() {
-        let board = Board::from_strings(sample_board());
+        let board = Board::from_strings(sample_board()).unwrap();
 
         for (pos, num) in [
             ((0, 0), 22),
@@ -122,7 +188,7 @@ mod tests {
     #[test]
     fn completed_row_wins() {
         // given
-        let mut board = Board::from_strings(sample_board());
+        let mut board = Board::from_strings(sample_board()).unwrap();
 
         // play middle row
         board.play(21);
@@ -137,58 +203,80 @@ mod tests {
 
     #[test]
     fn sets_up_a_game() {
-        let game = Game::from_strings(&sample_game());
+        let game = Game::from_strings(&sample_game()).unwrap();
         assert_eq!(27, game.turns.len());
         assert_eq!(3, game.boards.len());
     }
 
+    #[test]
+    fn ignores_blank_lines_after_the_last_board() {
+        let mut input = sample_game();
+        input.extend([String::new(), String::from("  ")]);
+        let mut game = Game::from_strings(&input).unwrap();
+        assert_eq!(3, game.boards.len());
+        assert_eq!(Some(1924), game.play_to_lose());
+    }
+
     #[test]
     fn plays_game_to_win() {
-        let mut game = Game::from_strings(&sample_game());
+        let mut game = Game::from_strings(&sample_game()).unwrap();
         assert_eq!(Some(4512), game.play_to_win());
     }
 
     #[test]
     fn plays_game_to_lose() {
-        let mut game = Game::from_strings(&sample_game());
+        let mut game = Game::from_strings(&sample_game()).unwrap();
         assert_eq!(Some(1924), game.play_to_lose());
     }
 
+    #[test]
+    fn reports_bad_board_number() {
+        let mut game = sample_game();
+        game[9] = String::from("19  8  7 2x 23");
+        let err = Game::from_strings(&game).err().unwrap();
+
+        assert_eq!((4, 10, 10), (err.day, err.line, err.column));
+        assert_eq!("2x", err.text);
+    }
+
+    #[test]
+    fn reports_short_board() {
+        let mut game = sample_game();
+        game.pop();
+        let err = Game::from_strings(&game).err().unwrap();
+
+        assert_eq!(18, err.line);
+        assert_eq!("expected 5 board rows, found 4", err.reason);
+    }
+
+    #[test]
+    fn reports_game_without_boards() {
+        let err = Game::from_strings(&[String::from("1,2,3"), String::new()])
+            .err()
+            .unwrap();
+
+        assert_eq!((4, 1, 1), (err.day, err.line, err.column));
+        assert_eq!("expected boards after the drawn numbers", err.reason);
+    }
+
+    #[test]
+    fn reports_boards_that_never_win() {
+        let mut game = sample_game()[..7].to_vec();
+        game[0] = String::from("22,13,17");
+        let day4 = Day4::parse(&game).unwrap();
+
+        let err = SolveError::NoAnswer("no board ever wins".to_string());
+        assert_eq!(Err(err), day4.part1());
+        let err = SolveError::NoAnswer("some board never wins".to_string());
+        assert_eq!(Some(Err(err)), day4.part2());
+    }
+
     fn sample_board() -> Vec<String> {
-        [
-            "22 13 17 11  0",
-            " 8  2 23  4 24",
-            "21  9 14 16  7",
-            " 6 10  3 18  5",
-            " 1 12 20 15 19",
-        ]
-        .map(|s| s.to_string())
-        .to_vec()
+        sample_game()[2..7].to_vec()
     }
 
     fn sample_game() -> Vec<String> {
-        [
-            "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1",
-            "",
-            "22 13 17 11  0",
-            " 8  2 23  4 24",
-            "21  9 14 16  7",
-            " 6 10  3 18  5",
-            " 1 12 20 15 19",
-            "",
-            " 3 15  0  2 22",
-            " 9 18 13 17  5",
-            "19  8  7 25 23",
-            "20 11 10 24  4",
-            "14 21 16 12  6",
-            "",
-            "14 21 17 24  4",
-            "10 16 15  9 19",
-            "18  8 23 26 20",
-            "22 11 13  6  5",
-            " 2  0 12  3  7",
-        ]
-        .map(|s| s.to_string())
-        .to_vec()
+        let (input, _) = examples::load(&examples::default_fixtures_dir(), DAY).unwrap();
+        input
     }
 }
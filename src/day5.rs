@@ -1,6 +1,11 @@
+use crate::parse::{self, Line, ParseResult};
+use crate::solution::{Solution, SolveResult};
 use std::collections::HashMap;
 
-type Point = (isize, isize);
+const DAY: u32 = 5;
+
+pub type Point = (isize, isize);
+pub type Segment = (Point, Point);
 
 #[derive(Default)]
 pub struct VentsMap {
@@ -13,17 +18,17 @@ impl VentsMap {
         *entry += 1;
     }
 
-    pub fn from_strings(input: &Vec<String>, include_diagonals: bool) -> VentsMap {
-        let mut result = VentsMap::default();
-
-        for line in input {
-            let ends = line
-                .split(" -> ")
-                .map(|s| parse_point(s))
-                .collect::<Vec<Point>>();
+    pub fn from_strings(input: &[String], include_diagonals: bool) -> ParseResult<VentsMap> {
+        Ok(VentsMap::from_segments(
+            &parse_segments(input)?,
+            include_diagonals,
+        ))
+    }
 
-            let (start, end) = (ends[0], ends[1]);
+    pub fn from_segments(segments: &[Segment], include_diagonals: bool) -> VentsMap {
+        let mut result = VentsMap::default();
 
+        for &(start, end) in segments {
             if start.0 == end.0 || start.1 == end.1 {
                 // horizontal or vertical line
                 for x in abs_range(start.0, end.0) {
@@ -32,8 +37,9 @@ impl VentsMap {
                     }
                 }
             } else {
-                // diagonal line
-                if include_diagonals {
+                // diagonal line; parsing rejects all but 45° ones, and
+                // segments built at any other angle miss the whole points
+                if include_diagonals && (end.0 - start.0).abs() == (end.1 - start.1).abs() {
                     for point in Diagonal::new(start, end) {
                         result.add(point);
                     }
@@ -48,12 +54,32 @@ impl VentsMap {
     }
 }
 
-fn parse_point(point_str: &str) -> Point {
-    let coords = point_str
-        .split(",")
-        .map(|n| n.parse::<isize>().unwrap())
-        .collect::<Vec<isize>>();
-    (coords[0], coords[1])
+fn parse_segments(input: &[String]) -> ParseResult<Vec<Segment>> {
+    parse::lines(DAY, input).map(parse_segment).collect()
+}
+
+fn parse_segment(line: Line) -> ParseResult<Segment> {
+    let (start, end) = line
+        .text
+        .split_once(" -> ")
+        .ok_or_else(|| line.error(line.text, "expected 'x1,y1 -> x2,y2'"))?;
+    let (start, end) = (parse_point(&line, start)?, parse_point(&line, end)?);
+
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    if dx != 0 && dy != 0 && dx.abs() != dy.abs() {
+        return Err(line.error(
+            line.text,
+            "line is not horizontal, vertical or 45° diagonal",
+        ));
+    }
+    Ok((start, end))
+}
+
+fn parse_point(line: &Line, point_str: &str) -> ParseResult<Point> {
+    let (x, y) = point_str
+        .split_once(',')
+        .ok_or_else(|| line.error(point_str, "expected 'x,y'"))?;
+    Ok((line.parse::<isize>(x)?, line.parse::<isize>(y)?))
 }
 
 fn abs_range(a: isize, b: isize) -> std::ops::RangeInclusive<isize> {
@@ -74,9 +100,9 @@ impl Diagonal {
         let y_delta = (end.1 - start.1).signum();
 
         Diagonal {
-            end: end,
-            x_delta: x_delta,
-            y_delta: y_delta,
+            end,
+            x_delta,
+            y_delta,
             current: (start.0 - x_delta, start.1 - y_delta),
         }
     }
@@ -94,31 +120,46 @@ impl Iterator for Diagonal {
     }
 }
 
+pub struct Day5 {
+    segments: Vec<Segment>,
+}
+
+impl Solution for Day5 {
+    fn parse(input: &[String]) -> ParseResult<Self> {
+        Ok(Day5 {
+            segments: parse_segments(input)?,
+        })
+    }
+
+    fn part1(&self) -> SolveResult {
+        VentsMap::from_segments(&self.segments, false)
+            .count_dangerous_areas()
+            .try_into()
+    }
+
+    fn part2(&self) -> Option<SolveResult> {
+        Some(
+            VentsMap::from_segments(&self.segments, true)
+                .count_dangerous_areas()
+                .try_into(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::examples;
 
     fn input() -> Vec<String> {
-        [
-            "0,9 -> 5,9",
-            "8,0 -> 0,8",
-            "9,4 -> 3,4",
-            "2,2 -> 2,1",
-            "7,0 -> 7,4",
-            "6,4 -> 2,0",
-            "0,9 -> 2,9",
-            "3,4 -> 1,4",
-            "0,0 -> 8,8",
-            "5,5 -> 8,2",
-        ]
-        .map(|s| String::from(s))
-        .to_vec()
+        let (input, _) = examples::load(&examples::default_fixtures_dir(), DAY).unwrap();
+        input
     }
 
     #[test]
     fn creates_map_of_points() {
         let input = input();
-        let vents_map = VentsMap::from_strings(&input, false);
+        let vents_map = VentsMap::from_strings(&input, false).unwrap();
 
         assert_eq!(vents_map.points[&(1, 9)], 2);
         assert_eq!(5, vents_map.count_dangerous_areas());
@@ -135,7 +176,31 @@ mod tests {
     #[test]
     fn finds_dangerous_areas_with_diagonals() {
         let input = input();
-        let vents_map = VentsMap::from_strings(&input, true);
+        let vents_map = VentsMap::from_strings(&input, true).unwrap();
         assert_eq!(12, vents_map.count_dangerous_areas());
     }
+
+    #[test]
+    fn rejects_lines_at_other_angles() {
+        let mut input = input();
+        input.push(String::from("0,0 -> 2,9"));
+        let err = VentsMap::from_strings(&input, true).err().unwrap();
+
+        assert_eq!((5, 11, 1), (err.day, err.line, err.column));
+        assert_eq!("0,0 -> 2,9", err.text);
+        assert_eq!(
+            "line is not horizontal, vertical or 45° diagonal",
+            err.reason
+        );
+    }
+
+    #[test]
+    fn reports_bad_coordinate() {
+        let mut input = input();
+        input[3] = String::from("2,2 -> 2,y");
+        let err = VentsMap::from_strings(&input, false).err().unwrap();
+
+        assert_eq!((5, 4, 10), (err.day, err.line, err.column));
+        assert_eq!("y", err.text);
+    }
 }
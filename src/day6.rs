@@ -1,28 +1,37 @@
+use crate::parse::{self, Line, ParseResult};
+use crate::solution::{Solution, SolveResult};
+
+const DAY: u32 = 6;
+
+#[derive(Clone)]
 pub struct School {
     pub day: i32,
     pub fish_counts: [i64; 9],
 }
 
 impl School {
-    pub fn from_string(fish_str: &str) -> School {
+    pub fn from_string(fish_str: &str) -> ParseResult<School> {
+        let line = Line::new(DAY, 1, fish_str);
         let mut fish_counts = [0; 9];
-        for n in fish_str.split(",") {
-            fish_counts[n.parse::<usize>().unwrap()] += 1;
+        for n in line.text.split(',') {
+            let timer = line.parse::<usize>(n)?;
+            if timer >= fish_counts.len() {
+                return Err(line.error(n, format!("timer {} is greater than 8", timer)));
+            }
+            fish_counts[timer] += 1;
         }
 
-        School {
+        Ok(School {
             day: 0,
-            fish_counts: fish_counts,
-        }
+            fish_counts,
+        })
     }
 
     pub fn next_day(&mut self) -> &School {
         let mut next_counts = [0; 9];
 
         // gestation periods get a day shorter
-        for i in 1..=8 {
-            next_counts[i - 1] = self.fish_counts[i];
-        }
+        next_counts[..8].copy_from_slice(&self.fish_counts[1..]);
 
         // day 0 fish have babies and reset their cycle
         next_counts[8] = self.fish_counts[0];
@@ -34,6 +43,7 @@ impl School {
         self
     }
 
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_day(&mut self, end_day: i32) -> &School {
         while self.day < end_day {
             self.next_day();
@@ -46,17 +56,48 @@ impl School {
     }
 }
 
+pub struct Day6 {
+    school: School,
+}
+
+impl Solution for Day6 {
+    fn parse(input: &[String]) -> ParseResult<Self> {
+        Ok(Day6 {
+            school: School::from_string(parse::first_line(DAY, input)?.text)?,
+        })
+    }
+
+    fn part1(&self) -> SolveResult {
+        Ok(self.school.clone().to_day(80).count().into())
+    }
+
+    fn part2(&self) -> Option<SolveResult> {
+        Some(Ok(self.school.clone().to_day(256).count().into()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::examples;
 
     #[test]
     fn calculates_generation() {
-        let mut school = School::from_string("3,4,3,1,2");
+        let (input, _) = examples::load(&examples::default_fixtures_dir(), DAY).unwrap();
+        let mut school = School::from_string(&input[0]).unwrap();
         assert_eq!(5, school.next_day().count());
         assert_eq!(6, school.next_day().count());
         assert_eq!(26, school.to_day(18).count());
         assert_eq!(5934, school.to_day(80).count());
         assert_eq!(26984457539, school.to_day(256).count());
     }
+
+    #[test]
+    fn rejects_bad_timers() {
+        let err = School::from_string("3,4,x,1").err().unwrap();
+        assert_eq!((6, 1, 5), (err.day, err.line, err.column));
+
+        let err = School::from_string("3,4,9,1").err().unwrap();
+        assert_eq!("timer 9 is greater than 8", err.reason);
+    }
 }
@@ -1,16 +1,33 @@
-pub fn histogram(input: &str) -> Vec<isize> {
-    let crabs = input
-        .split(",")
-        .map(|n| n.parse::<usize>().unwrap())
-        .collect::<Vec<usize>>();
+use crate::parse::{self, Line, ParseResult};
+use crate::solution::{Solution, SolveResult};
+
+const DAY: u32 = 7;
+
+/// The furthest position a crab can be at. The histogram has a slot for
+/// every position up to the furthest crab, and finding the least cost
+/// takes time proportional to the square of that.
+pub const MAX_POSITION: usize = 10_000;
+
+pub fn histogram(input: &str) -> ParseResult<Vec<isize>> {
+    let line = Line::new(DAY, 1, input);
+    let crabs = line
+        .text
+        .split(',')
+        .map(|n| match line.parse::<usize>(n)? {
+            crab if crab > MAX_POSITION => {
+                Err(line.error(n, format!("crab position above {}", MAX_POSITION)))
+            }
+            crab => Ok(crab),
+        })
+        .collect::<ParseResult<Vec<usize>>>()?;
     let max = crabs.iter().max().unwrap();
-    crabs.iter().fold(vec![0; *max + 1], |mut acc, crab| {
+    Ok(crabs.iter().fold(vec![0; *max + 1], |mut acc, crab| {
         acc[*crab] += 1;
         acc
-    })
+    }))
 }
 
-type FuelCostFn = fn(isize, isize) -> isize;
+pub type FuelCostFn = fn(isize, isize) -> isize;
 
 fn linear_cost(a: isize, b: isize) -> isize {
     (a - b).abs()
@@ -42,24 +59,48 @@ pub fn stepped_least_cost(histogram: &[isize]) -> (usize, isize) {
     least_cost(histogram, stepped_cost)
 }
 
+pub struct Day7 {
+    histogram: Vec<isize>,
+}
+
+impl Solution for Day7 {
+    fn parse(input: &[String]) -> ParseResult<Self> {
+        Ok(Day7 {
+            histogram: histogram(parse::first_line(DAY, input)?.text)?,
+        })
+    }
+
+    fn part1(&self) -> SolveResult {
+        let (_col, cost) = linear_least_cost(&self.histogram);
+        cost.try_into()
+    }
+
+    fn part2(&self) -> Option<SolveResult> {
+        let (_col, cost) = stepped_least_cost(&self.histogram);
+        Some(cost.try_into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::examples;
 
-    fn input() -> &'static str {
-        "16,1,2,0,4,2,7,1,2,14"
+    fn input() -> String {
+        let (input, _) = examples::load(&examples::default_fixtures_dir(), DAY).unwrap();
+        input[0].clone()
     }
 
     #[test]
     fn builds_histogram() {
-        let histogram = histogram(input());
+        let histogram = histogram(&input()).unwrap();
         assert_eq!(17, histogram.len());
         assert_eq!([1, 2, 3], histogram[0..=2]);
     }
 
     #[test]
     fn calculates_linear_fuel_cost() {
-        let histogram = histogram(input());
+        let histogram = histogram(&input()).unwrap();
 
         assert_eq!(37, fuel_cost(&histogram, 2, linear_cost));
         assert_eq!(41, fuel_cost(&histogram, 1, linear_cost));
@@ -68,20 +109,37 @@ mod tests {
 
     #[test]
     fn calculates_stepped_fuel_cost() {
-        let histogram = histogram(input());
+        let histogram = histogram(&input()).unwrap();
         assert_eq!(206, fuel_cost(&histogram, 2, stepped_cost));
         assert_eq!(168, fuel_cost(&histogram, 5, stepped_cost));
     }
 
     #[test]
     fn finds_linear_least_cost() {
-        let histogram = histogram(input());
+        let histogram = histogram(&input()).unwrap();
         assert_eq!((2, 37), linear_least_cost(&histogram));
     }
 
     #[test]
     fn finds_stepped_least_cost() {
-        let histogram = histogram(input());
+        let histogram = histogram(&input()).unwrap();
         assert_eq!((5, 168), stepped_least_cost(&histogram));
     }
+
+    #[test]
+    fn reports_bad_position() {
+        let err = histogram("16,1,-2,0").unwrap_err();
+        assert_eq!((7, 1, 6), (err.day, err.line, err.column));
+        assert_eq!("-2", err.text);
+    }
+
+    #[test]
+    fn reports_positions_too_far_for_the_histogram() {
+        for far in ["10001", "9999999999999999999"] {
+            let err = histogram(&format!("16,{},0", far)).unwrap_err();
+            assert_eq!((7, 1, 4), (err.day, err.line, err.column));
+            assert_eq!(far, err.text);
+            assert_eq!("crab position above 10000", err.reason);
+        }
+    }
 }
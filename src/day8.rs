@@ -1,7 +1,14 @@
-fn extract_output_values<'a>(line: &'a str) -> Vec<&'a str> {
-    line.split(" | ").collect::<Vec<&str>>()[1]
-        .split_whitespace()
-        .collect::<Vec<&str>>()
+use crate::parse::{Line, ParseResult};
+use crate::solution::{Solution, SolveResult};
+
+const DAY: u32 = 8;
+
+fn extract_output_values<'a>(line: &Line<'a>) -> ParseResult<Vec<&'a str>> {
+    let (_patterns, outputs) = line
+        .text
+        .split_once(" | ")
+        .ok_or_else(|| line.missing("' | ' before output values"))?;
+    Ok(outputs.split_whitespace().collect::<Vec<&str>>())
 }
 
 fn count_lengths(values: &[&str], lengths: &[usize]) -> usize {
@@ -11,31 +18,56 @@ fn count_lengths(values: &[&str], lengths: &[usize]) -> usize {
         .count()
 }
 
-pub fn count_outputs_with_lengths(input: &[&str], lengths: &[usize]) -> usize {
+fn lines<'a>(input: &'a [&'a str]) -> impl Iterator<Item = Line<'a>> {
     input
         .iter()
-        .map(|line| extract_output_values(line))
-        .map(|values| count_lengths(&values, lengths))
+        .enumerate()
+        .map(|(i, text)| Line::new(DAY, i + 1, text))
+}
+
+pub fn count_outputs_with_lengths(input: &[&str], lengths: &[usize]) -> ParseResult<usize> {
+    lines(input)
+        .map(|line| extract_output_values(&line))
+        .map(|values| Ok(count_lengths(&values?, lengths)))
         .sum()
 }
 
+pub struct Day8 {
+    outputs: Vec<Vec<String>>,
+}
+
+impl Solution for Day8 {
+    fn parse(input: &[String]) -> ParseResult<Self> {
+        let input = input.iter().map(|s| s as &str).collect::<Vec<&str>>();
+        let outputs = lines(&input)
+            .map(|line| {
+                let values = extract_output_values(&line)?;
+                Ok(values.into_iter().map(String::from).collect())
+            })
+            .collect::<ParseResult<Vec<Vec<String>>>>()?;
+        Ok(Day8 { outputs })
+    }
+
+    fn part1(&self) -> SolveResult {
+        self.outputs
+            .iter()
+            .map(|values| {
+                let values = values.iter().map(|s| s as &str).collect::<Vec<&str>>();
+                count_lengths(&values, &[2, 4, 3, 7])
+            })
+            .sum::<usize>()
+            .try_into()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::examples;
 
-    fn input() -> Vec<&'static str> {
-        vec![
-            "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe",
-            "edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc",
-            "fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg",
-            "fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb",
-            "aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea",
-            "fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb",
-            "dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe",
-            "bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef",
-            "egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb",
-            "gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce",
-        ]
+    fn input() -> Vec<String> {
+        let (input, _) = examples::load(&examples::default_fixtures_dir(), DAY).unwrap();
+        input
     }
 
     #[test]
@@ -43,7 +75,7 @@ mod tests {
         let line = "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe";
         assert_eq!(
             vec!["fdgacbe", "cefdb", "cefbgd", "gcbe"],
-            extract_output_values(line)
+            extract_output_values(&Line::new(DAY, 1, line)).unwrap()
         );
     }
 
@@ -56,6 +88,21 @@ mod tests {
 
     #[test]
     fn counts_unusual_digits() {
-        assert_eq!(26, count_outputs_with_lengths(&input(), &[2, 4, 3, 7]));
+        let input = input();
+        let lines = input.iter().map(String::as_str).collect::<Vec<&str>>();
+        assert_eq!(
+            26,
+            count_outputs_with_lengths(&lines, &[2, 4, 3, 7]).unwrap()
+        );
+    }
+
+    #[test]
+    fn reports_missing_output_values() {
+        let input = input();
+        let mut lines = input.iter().map(String::as_str).collect::<Vec<&str>>();
+        lines[2] = "fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef";
+        let err = count_outputs_with_lengths(&lines, &[2, 4, 3, 7]).unwrap_err();
+
+        assert_eq!((8, 3, 59), (err.day, err.line, err.column));
     }
 }
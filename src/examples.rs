@@ -0,0 +1,199 @@
+//! Example inputs and answers pulled from saved puzzle pages
+//!
+//! A puzzle page shows each example in a `<pre><code>` block and
+//! highlights each part's example answer as the last `<code><em>` in that
+//! part's `<article class="day-desc">`. `extract` finds both, and `save`
+//! stores them as fixtures:
+//!
+//! fixtures/day5/example1.txt, example2.txt, ...
+//! fixtures/day5/answers.toml   (expected answers for example1.txt)
+use crate::answers::{Answers, AnswersError};
+use crate::input::Source;
+use crate::solution::Answer;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq, Default)]
+pub struct PuzzlePage {
+    /// Text of each distinct example block, in page order
+    pub examples: Vec<String>,
+    /// The example answer for each part found on the page
+    pub answers: Vec<String>,
+}
+
+pub fn default_fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+pub fn extract(html: &str) -> PuzzlePage {
+    let mut page = PuzzlePage::default();
+
+    for block in elements(html, "<pre><code>", "</code></pre>") {
+        let example = decode(&strip_tags(block));
+        if !page.examples.contains(&example) {
+            page.examples.push(example);
+        }
+    }
+    for article in elements(html, "<article class=\"day-desc\">", "</article>") {
+        if let Some(answer) = elements(article, "<code><em>", "</em></code>").last() {
+            page.answers.push(decode(&strip_tags(answer)));
+        }
+    }
+    page
+}
+
+/// Write the page's examples and answers under `fixtures_dir/dayN`,
+/// returning the files written
+pub fn save(page: &PuzzlePage, fixtures_dir: &Path, day: u32) -> io::Result<Vec<PathBuf>> {
+    let dir = fixtures_dir.join(format!("day{}", day));
+    fs::create_dir_all(&dir)?;
+    let mut written = Vec::new();
+
+    for (i, example) in page.examples.iter().enumerate() {
+        let path = dir.join(format!("example{}.txt", i + 1));
+        fs::write(&path, example)?;
+        written.push(path);
+    }
+
+    if !page.answers.is_empty() {
+        let mut answers = Answers::default();
+        for (i, answer) in page.answers.iter().enumerate() {
+            let answer = match answer.parse::<i64>() {
+                Ok(n) => Answer::Number(n),
+                Err(_) => Answer::Text(answer.clone()),
+            };
+            answers.record(day, u32::try_from(i + 1).unwrap(), answer);
+        }
+        let path = dir.join("answers.toml");
+        fs::write(&path, answers.to_toml())?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// The first example input for `day` under `fixtures_dir` and its
+/// expected answers, as `save` left them
+pub fn load(fixtures_dir: &Path, day: u32) -> Result<(Vec<String>, Answers), AnswersError> {
+    let dir = fixtures_dir.join(format!("day{}", day));
+    let input = Source::File(dir.join("example1.txt"))
+        .read()
+        .map_err(AnswersError::Io)?;
+    let answers = Answers::load(&dir.join("answers.toml"))?;
+    Ok((input, answers))
+}
+
+/// The contents of each non-nested `open`...`close` element
+fn elements<'a>(html: &'a str, open: &str, close: &str) -> Vec<&'a str> {
+    let mut result = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find(open) {
+        let after = &rest[start + open.len()..];
+        match after.find(close) {
+            Some(end) => {
+                result.push(&after[..end]);
+                rest = &after[end + close.len()..];
+            }
+            None => break,
+        }
+    }
+    result
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut result = String::new();
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => result.push(ch),
+            _ => {}
+        }
+    }
+    result
+}
+
+fn decode(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution;
+
+    const PAGE: &str = r#"<html><body><main>
+<article class="day-desc"><h2>--- Day 1: Sonar Sweep ---</h2>
+<p>For example, suppose you had the following report:</p>
+<pre><code>199
+200
+<em>208</em>
+</code></pre>
+<p>In this example, there are <code><em>7</em></code> measurements that are larger.</p>
+</article>
+<p>Your puzzle answer was <code>1393</code>.</p>
+<article class="day-desc"><h2 id="part2">--- Part Two ---</h2>
+<pre><code>199  A
+200  A B
+</code></pre>
+<p>Compare <code>a &lt; b</code>: there are <code><em>5</em></code> sums.</p>
+</article>
+</main></body></html>"#;
+
+    #[test]
+    fn extracts_example_blocks() {
+        let page = extract(PAGE);
+        assert_eq!(vec!["199\n200\n208\n", "199  A\n200  A B\n"], page.examples);
+    }
+
+    #[test]
+    fn extracts_last_highlighted_answer_per_part() {
+        assert_eq!(vec!["7", "5"], extract(PAGE).answers);
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!("a < b && c", decode("a &lt; b &amp;&amp; c"));
+    }
+
+    #[test]
+    fn saves_fixtures() {
+        let dir = std::env::temp_dir().join(format!("advent2021-fixtures-{}", std::process::id()));
+        let written = save(&extract(PAGE), &dir, 1).unwrap();
+
+        assert_eq!(3, written.len());
+        assert_eq!(
+            "199\n200\n208\n",
+            fs::read_to_string(dir.join("day1/example1.txt")).unwrap()
+        );
+        let answers = Answers::load(&dir.join("day1/answers.toml")).unwrap();
+        assert_eq!(Some(&Answer::Number(5)), answers.get(1, 2));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn every_day_solves_its_example() {
+        for registration in solution::SOLUTIONS {
+            let (input, answers) = load(&default_fixtures_dir(), registration.day).unwrap();
+            let solution = (registration.parse)(&input).unwrap();
+            for part in 1..=2 {
+                if let (Some(actual), Some(expected)) =
+                    (solution.solve(part), answers.get(registration.day, part))
+                {
+                    assert_eq!(
+                        Ok(expected),
+                        actual.as_ref(),
+                        "day {} part {} example",
+                        registration.day,
+                        part
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Downloads puzzle inputs into the input directory
+//!
+//! Advent of Code serves each user's input behind their session cookie. The
+//! token comes from `ADVENT_SESSION`, or failing that from the file
+//! `~/.config/advent2021/session`. Inputs already on disk are never fetched
+//! again.
+use crate::input;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+pub const BASE_URL_VAR: &str = "ADVENT_BASE_URL";
+pub const SESSION_VAR: &str = "ADVENT_SESSION";
+
+/// Relative to the home directory
+const SESSION_FILE: &str = ".config/advent2021/session";
+const USER_AGENT: &str = "advent2021 (https://github.com/tastapod/advent-2021)";
+
+#[derive(Debug)]
+pub enum FetchError {
+    NoSession,
+    Status { url: String, status: u16 },
+    Transport { url: String, message: String },
+    Io(io::Error),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchError::NoSession => write!(
+                f,
+                "no session token: set {} or write it to ~/{}",
+                SESSION_VAR, SESSION_FILE
+            ),
+            FetchError::Status { url, status } => write!(f, "{} returned HTTP {}", url, status),
+            FetchError::Transport { url, message } => write!(f, "{}: {}", url, message),
+            FetchError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Fetched {
+    Downloaded(PathBuf),
+    Cached(PathBuf),
+}
+
+pub fn default_base_url() -> String {
+    match env::var(BASE_URL_VAR) {
+        Ok(url) if !url.is_empty() => url,
+        _ => DEFAULT_BASE_URL.to_string(),
+    }
+}
+
+fn session_file() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| Path::new(&home).join(SESSION_FILE))
+}
+
+/// The session token from the environment, else from the config file
+pub fn session_token() -> Result<String, FetchError> {
+    if let Ok(token) = env::var(SESSION_VAR) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+    let path = session_file().ok_or(FetchError::NoSession)?;
+    match fs::read_to_string(path) {
+        Ok(token) if !token.trim().is_empty() => Ok(token.trim().to_string()),
+        _ => Err(FetchError::NoSession),
+    }
+}
+
+pub fn input_url(base_url: &str, day: u32) -> String {
+    format!("{}/2021/day/{}/input", base_url.trim_end_matches('/'), day)
+}
+
+/// Download a day's input into `dir` unless it is already there.
+/// `session` is only asked for when a download is needed.
+pub fn fetch_input(
+    base_url: &str,
+    session: &dyn Fn() -> Result<String, FetchError>,
+    dir: &Path,
+    day: u32,
+) -> Result<Fetched, FetchError> {
+    let path = input::path_for_day(dir, day);
+    if path.exists() {
+        return Ok(Fetched::Cached(path));
+    }
+
+    let url = input_url(base_url, day);
+    let response = ureq::get(&url)
+        .set("Cookie", &format!("session={}", session()?))
+        .set("User-Agent", USER_AGENT)
+        .call()
+        .map_err(|err| match err {
+            ureq::Error::Status(status, _) => FetchError::Status {
+                url: url.clone(),
+                status,
+            },
+            ureq::Error::Transport(transport) => FetchError::Transport {
+                url: url.clone(),
+                message: transport.to_string(),
+            },
+        })?;
+    let body = response.into_string().map_err(FetchError::Io)?;
+
+    // write then rename, so an interrupted download never looks cached
+    fs::create_dir_all(dir).map_err(FetchError::Io)?;
+    let partial = path.with_extension("txt.part");
+    fs::write(&partial, body).map_err(FetchError::Io)?;
+    fs::rename(&partial, &path).map_err(FetchError::Io)?;
+    Ok(Fetched::Downloaded(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serve one canned response, handing back the request headers
+    fn serve_once(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = String::new();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                request.push_str(&line);
+            }
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+            request
+        });
+        (base_url, handle)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("advent2021-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn token() -> Result<String, FetchError> {
+        Ok("abc123".to_string())
+    }
+
+    #[test]
+    fn builds_input_url() {
+        assert_eq!(
+            "https://adventofcode.com/2021/day/5/input",
+            input_url("https://adventofcode.com/", 5)
+        );
+    }
+
+    #[test]
+    fn downloads_input_with_session_cookie() {
+        let (base_url, server) = serve_once("200 OK", "199\n200\n208\n");
+        let dir = temp_dir("download");
+
+        let fetched = fetch_input(&base_url, &token, &dir, 1).unwrap();
+
+        let path = dir.join("day1_input.txt");
+        assert_eq!(Fetched::Downloaded(path.clone()), fetched);
+        assert_eq!("199\n200\n208\n", fs::read_to_string(&path).unwrap());
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET /2021/day/1/input "));
+        assert!(request.contains("session=abc123"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn never_refetches_cached_input() {
+        let dir = temp_dir("cached");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("day2_input.txt"), "forward 5\n").unwrap();
+        let no_session = || -> Result<String, FetchError> { panic!("should not need a session") };
+
+        let fetched = fetch_input("http://127.0.0.1:9", &no_session, &dir, 2).unwrap();
+
+        assert_eq!(Fetched::Cached(dir.join("day2_input.txt")), fetched);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reports_http_errors_without_writing() {
+        let (base_url, server) = serve_once(
+            "404 Not Found",
+            "Please don't repeatedly request this endpoint",
+        );
+        let dir = temp_dir("missing");
+
+        let err = fetch_input(&base_url, &token, &dir, 25).unwrap_err();
+
+        assert!(matches!(err, FetchError::Status { status: 404, .. }));
+        assert!(!dir.join("day25_input.txt").exists());
+        server.join().unwrap();
+    }
+}
@@ -1,12 +1,57 @@
+use std::env;
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufRead};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-pub fn for_day(day: i32) -> Vec<String> {
-    read_lines(format!("src/day{}_input.txt", day))
-        .unwrap()
-        .map(|s| s.unwrap())
-        .collect::<Vec<String>>()
+/// Environment variable naming the directory that holds dayN_input.txt files
+pub const DIR_VAR: &str = "ADVENT_INPUT_DIR";
+
+/// Where a day's puzzle input comes from
+#[derive(Debug, PartialEq, Clone)]
+pub enum Source {
+    File(PathBuf),
+    Stdin,
+}
+
+impl Source {
+    /// "-" means stdin, anything else is a file path
+    pub fn from_arg(arg: &str) -> Source {
+        if arg == "-" {
+            Source::Stdin
+        } else {
+            Source::File(PathBuf::from(arg))
+        }
+    }
+
+    pub fn read(&self) -> io::Result<Vec<String>> {
+        match self {
+            Source::File(path) => read_lines(path)?.collect(),
+            Source::Stdin => io::stdin().lock().lines().collect(),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Stdin => write!(f, "stdin"),
+        }
+    }
+}
+
+/// The input directory from `ADVENT_INPUT_DIR`, falling back to this
+/// crate's src/ so the binary works from any working directory.
+pub fn default_dir() -> PathBuf {
+    match env::var_os(DIR_VAR) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+    }
+}
+
+pub fn path_for_day(dir: &Path, day: u32) -> PathBuf {
+    dir.join(format!("day{}_input.txt", day))
 }
 
 /// from https://doc.rust-lang.org/stable/rust-by-example/std_misc/file/read_lines.html
@@ -17,3 +62,38 @@ where
     let file = File::open(filename)?;
     Ok(io::BufReader::new(file).lines())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_source_args() {
+        assert_eq!(Source::Stdin, Source::from_arg("-"));
+        assert_eq!(
+            Source::File(PathBuf::from("inputs/day5.txt")),
+            Source::from_arg("inputs/day5.txt")
+        );
+    }
+
+    #[test]
+    fn names_day_files_in_dir() {
+        assert_eq!(
+            PathBuf::from("inputs/day7_input.txt"),
+            path_for_day(Path::new("inputs"), 7)
+        );
+    }
+
+    #[test]
+    fn reads_day_from_crate_src() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let lines = Source::File(path_for_day(&dir, 1)).read().unwrap();
+        assert_eq!("156", lines[0]);
+    }
+
+    #[test]
+    fn reports_missing_file() {
+        let missing = Source::File(path_for_day(Path::new("no/such/dir"), 1)).read();
+        assert_eq!(io::ErrorKind::NotFound, missing.unwrap_err().kind());
+    }
+}
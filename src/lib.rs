@@ -0,0 +1,21 @@
+//! Solutions to Advent of Code 2021, usable outside the `advent2021` binary
+//!
+//! Each `dayN` module solves one puzzle and registers a `Solution` in
+//! `solution::SOLUTIONS`. The remaining modules read inputs, report parse
+//! errors and check answers, and are what the command-line runner is built on.
+pub mod answers;
+pub mod examples;
+pub mod fetch;
+pub mod input;
+pub mod parse;
+pub mod solution;
+pub mod timing;
+
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
@@ -0,0 +1,183 @@
+//! Shared error reporting for the day parsers
+//!
+//! Parsers walk their input as numbered `Line`s, so any failure can say
+//! exactly which day, line and column it came from.
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub day: u32,
+    /// 1-based line number in the puzzle input
+    pub line: usize,
+    /// 1-based character column of the offending text
+    pub column: usize,
+    /// The offending text itself
+    pub text: String,
+    pub reason: String,
+    /// The whole input line, for diagnostics
+    pub source_line: String,
+}
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+impl ParseError {
+    /// A multi-line report pointing at the offending text, compiler style.
+    /// `origin` names where the input came from, such as its file path.
+    pub fn diagnostic(&self, origin: &dyn fmt::Display) -> String {
+        let gutter = self.line.to_string().len();
+        let marker = "^".repeat(self.text.chars().count().max(1));
+        format!(
+            "error: {}\n{:>w$}--> {}:{}:{}\n{:>w$} |\n{} | {}\n{:>w$} | {}{}",
+            self,
+            "",
+            origin,
+            self.line,
+            self.column,
+            "",
+            self.line,
+            self.source_line,
+            "",
+            " ".repeat(self.column - 1),
+            marker,
+            w = gutter
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "day {}, line {}, column {}: {}",
+            self.day, self.line, self.column, self.reason
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// One line of a day's input, remembering where it came from
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+    pub day: u32,
+    pub number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    pub fn new(day: u32, number: usize, text: &'a str) -> Self {
+        Line { day, number, text }
+    }
+
+    /// Character column of `token`, which must be a slice of this line
+    pub fn column_of(&self, token: &str) -> usize {
+        let start = self.text.as_ptr() as usize;
+        let offset = (token.as_ptr() as usize)
+            .checked_sub(start)
+            .filter(|offset| *offset <= self.text.len())
+            .unwrap_or(0);
+        self.text[..offset].chars().count() + 1
+    }
+
+    pub fn error(&self, token: &str, reason: impl Into<String>) -> ParseError {
+        ParseError {
+            day: self.day,
+            line: self.number,
+            column: self.column_of(token),
+            text: token.to_string(),
+            reason: reason.into(),
+            source_line: self.text.to_string(),
+        }
+    }
+
+    /// An error about something missing from the end of this line
+    pub fn missing(&self, what: &str) -> ParseError {
+        let end = &self.text[self.text.len()..];
+        self.error(end, format!("missing {}", what))
+    }
+
+    /// Parse `token`, a slice of this line, into a number or other value
+    pub fn parse<T: FromStr>(&self, token: &str) -> ParseResult<T> {
+        token
+            .parse::<T>()
+            .map_err(|_| self.error(token, format!("cannot parse '{}'", token)))
+    }
+}
+
+/// Number the lines of a day's input from 1
+pub fn lines(day: u32, input: &[String]) -> impl Iterator<Item = Line<'_>> {
+    input
+        .iter()
+        .enumerate()
+        .map(move |(i, text)| Line::new(day, i + 1, text))
+}
+
+/// The first line of `input`, or an error if there isn't one
+pub fn first_line(day: u32, input: &[String]) -> ParseResult<Line<'_>> {
+    lines(day, input)
+        .next()
+        .ok_or_else(|| Line::new(day, 1, "").missing("input"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_token_column() {
+        let line = Line::new(4, 3, "22 13 x1 11");
+        let token = line.text.split_whitespace().nth(2).unwrap();
+        assert_eq!(7, line.column_of(token));
+    }
+
+    #[test]
+    fn reports_bad_number() {
+        let line = Line::new(4, 3, "22 13 x1 11");
+        let token = line.text.split_whitespace().nth(2).unwrap();
+        let err = line.parse::<usize>(token).unwrap_err();
+
+        assert_eq!(
+            ParseError {
+                day: 4,
+                line: 3,
+                column: 7,
+                text: String::from("x1"),
+                reason: String::from("cannot parse 'x1'"),
+                source_line: String::from("22 13 x1 11"),
+            },
+            err
+        );
+        assert_eq!(
+            "day 4, line 3, column 7: cannot parse 'x1'",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn points_at_error_in_diagnostic() {
+        let line = Line::new(4, 3, "22 13 x1 11");
+        let err = line.error(&line.text[6..8], "cannot parse 'x1'");
+        assert_eq!(
+            "error: day 4, line 3, column 7: cannot parse 'x1'\n --> day4.txt:3:7\n  |\n3 | 22 13 x1 11\n  |       ^^",
+            err.diagnostic(&"day4.txt")
+        );
+    }
+
+    #[test]
+    fn reports_missing_text_at_end_of_line() {
+        let line = Line::new(2, 1, "forward");
+        let err = line.missing("distance");
+        assert_eq!(8, err.column);
+        assert_eq!("missing distance", err.reason);
+    }
+
+    #[test]
+    fn numbers_lines_from_one() {
+        let input = ["a", "b"].map(String::from);
+        let numbers = lines(1, &input).map(|l| l.number).collect::<Vec<usize>>();
+        assert_eq!(vec![1, 2], numbers);
+        assert!(first_line(1, &[]).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+//! Machine-readable runner output: one record per day and part
+//!
+//! JSON is an array with one object per line:
+//!
+//! [
+//!   {"day": 1, "part": 1, "answer": 1393, "type": "number", "parse_ns": 81000, "solve_ns": 1200, "error": null}
+//! ]
+//!
+//! CSV has the same fields as columns, under a header row.
+use advent2021::solution::Answer;
+use std::time::Duration;
+
+const CSV_HEADER: &str = "day,part,answer,type,parse_ns,solve_ns,error";
+
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    pub day: u32,
+    pub part: u32,
+    pub answer: Option<Answer>,
+    pub parse: Option<Duration>,
+    pub solve: Option<Duration>,
+    pub error: Option<String>,
+}
+
+impl Record {
+    pub fn solved(day: u32, part: u32, answer: Answer, parse: Duration, solve: Duration) -> Self {
+        Record {
+            day,
+            part,
+            answer: Some(answer),
+            parse: Some(parse),
+            solve: Some(solve),
+            error: None,
+        }
+    }
+
+    pub fn failed(day: u32, part: u32, error: String) -> Self {
+        Record {
+            day,
+            part,
+            answer: None,
+            parse: None,
+            solve: None,
+            error: Some(error),
+        }
+    }
+
+    fn answer_type(&self) -> Option<&'static str> {
+        self.answer.as_ref().map(|answer| match answer {
+            Answer::Number(_) => "number",
+            Answer::Text(_) => "text",
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let answer = match &self.answer {
+            Some(Answer::Number(n)) => n.to_string(),
+            Some(Answer::Text(s)) => json_string(s),
+            None => "null".to_string(),
+        };
+        let nanos = |duration: Option<Duration>| {
+            duration.map_or("null".to_string(), |d| d.as_nanos().to_string())
+        };
+        format!(
+            "{{\"day\": {}, \"part\": {}, \"answer\": {}, \"type\": {}, \"parse_ns\": {}, \"solve_ns\": {}, \"error\": {}}}",
+            self.day,
+            self.part,
+            answer,
+            self.answer_type().map_or("null".to_string(), json_string),
+            nanos(self.parse),
+            nanos(self.solve),
+            self.error.as_deref().map_or("null".to_string(), json_string),
+        )
+    }
+
+    pub fn to_csv(&self) -> String {
+        let nanos = |duration: Option<Duration>| {
+            duration.map_or(String::new(), |d| d.as_nanos().to_string())
+        };
+        [
+            self.day.to_string(),
+            self.part.to_string(),
+            self.answer
+                .as_ref()
+                .map_or(String::new(), |answer| csv_field(&answer.to_string())),
+            self.answer_type().unwrap_or_default().to_string(),
+            nanos(self.parse),
+            nanos(self.solve),
+            self.error.as_deref().map_or(String::new(), csv_field),
+        ]
+        .join(",")
+    }
+}
+
+pub fn to_json(records: &[Record]) -> String {
+    if records.is_empty() {
+        return "[]\n".to_string();
+    }
+    let lines = records
+        .iter()
+        .map(|record| format!("  {}", record.to_json()))
+        .collect::<Vec<String>>();
+    format!("[\n{}\n]\n", lines.join(",\n"))
+}
+
+pub fn to_csv(records: &[Record]) -> String {
+    let mut result = format!("{}\n", CSV_HEADER);
+    for record in records {
+        result.push_str(&record.to_csv());
+        result.push('\n');
+    }
+    result
+}
+
+fn json_string(text: &str) -> String {
+    let mut result = String::from('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Quote fields containing separators, quotes or line breaks
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record::solved(
+                1,
+                1,
+                Answer::Number(1393),
+                Duration::from_micros(81),
+                Duration::from_nanos(1200),
+            ),
+            Record::solved(
+                3,
+                2,
+                Answer::Text("a \"b\", c".to_string()),
+                Duration::from_nanos(5),
+                Duration::from_nanos(7),
+            ),
+            Record::failed(4, 1, "input.txt: day 4, line 3, column 1: bad".to_string()),
+        ]
+    }
+
+    #[test]
+    fn writes_json_records() {
+        assert_eq!(
+            "[\n  \
+             {\"day\": 1, \"part\": 1, \"answer\": 1393, \"type\": \"number\", \"parse_ns\": 81000, \"solve_ns\": 1200, \"error\": null},\n  \
+             {\"day\": 3, \"part\": 2, \"answer\": \"a \\\"b\\\", c\", \"type\": \"text\", \"parse_ns\": 5, \"solve_ns\": 7, \"error\": null},\n  \
+             {\"day\": 4, \"part\": 1, \"answer\": null, \"type\": null, \"parse_ns\": null, \"solve_ns\": null, \"error\": \"input.txt: day 4, line 3, column 1: bad\"}\n\
+             ]\n",
+            to_json(&records())
+        );
+        assert_eq!("[]\n", to_json(&[]));
+    }
+
+    #[test]
+    fn writes_csv_records() {
+        assert_eq!(
+            "day,part,answer,type,parse_ns,solve_ns,error\n\
+             1,1,1393,number,81000,1200,\n\
+             3,2,\"a \"\"b\"\", c\",text,5,7,\n\
+             4,1,,,,,\"input.txt: day 4, line 3, column 1: bad\"\n",
+            to_csv(&records())
+        );
+    }
+
+    #[test]
+    fn escapes_control_characters_in_json() {
+        assert_eq!("\"a\\tb\\u0001\\\\\"", json_string("a\tb\u{1}\\"));
+    }
+}
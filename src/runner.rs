@@ -0,0 +1,402 @@
+//! Loads each selected day's input and runs its registered solution
+use crate::cli::{AnalyzeArgs, BenchArgs, ExamplesArgs, FetchArgs, Format, RunArgs, VerifyArgs};
+use crate::record::{self, Record};
+use crate::table::Table;
+use advent2021::answers::{Answers, AnswersError, Verdict};
+use advent2021::day1;
+use advent2021::examples;
+use advent2021::fetch::{self, FetchError, Fetched};
+use advent2021::input::{self, Source};
+use advent2021::parse::ParseError;
+use advent2021::solution::{self, Answer, Registration, Solution, SolveError};
+use advent2021::timing::{self, format_duration, Stats};
+use std::cmp::Reverse;
+use std::fmt;
+use std::hint::black_box;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum RunError {
+    Input {
+        day: u32,
+        source: Source,
+        err: io::Error,
+    },
+    Parse {
+        source: Source,
+        err: ParseError,
+    },
+    Answers {
+        path: PathBuf,
+        err: AnswersError,
+    },
+    Solve {
+        day: u32,
+        part: u32,
+        err: SolveError,
+    },
+    Fetch {
+        day: u32,
+        err: FetchError,
+    },
+    Examples {
+        path: PathBuf,
+        err: io::Error,
+    },
+}
+
+impl RunError {
+    /// A message suitable for printing to stderr
+    pub fn report(&self) -> String {
+        match self {
+            RunError::Parse { source, err } => err.diagnostic(source),
+            _ => format!("error: {}", self),
+        }
+    }
+}
+
+/// A one-line summary; `report` gives parse errors in full
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Input { day, source, err } => write!(
+                f,
+                "cannot read input for day {} from {}: {}",
+                day, source, err
+            ),
+            RunError::Parse { source, err } => write!(f, "{}: {}", source, err),
+            RunError::Answers { path, err } => {
+                write!(f, "answers file {}: {}", path.display(), err)
+            }
+            RunError::Solve { day, part, err } => write!(f, "day {} part {}: {}", day, part, err),
+            RunError::Fetch { day, err } => {
+                write!(f, "cannot fetch input for day {}: {}", day, err)
+            }
+            RunError::Examples { path, err } => write!(f, "{}: {}", path.display(), err),
+        }
+    }
+}
+
+struct DayInput {
+    registration: &'static Registration,
+    source: Source,
+    lines: Vec<String>,
+}
+
+impl DayInput {
+    fn read(args: &RunArgs, day: u32) -> Result<DayInput, RunError> {
+        let registration = solution::for_day(day).expect("cli only selects registered days");
+        let source = match &args.input {
+            Some(source) => source.clone(),
+            None => Source::File(input::path_for_day(&args.input_dir, day)),
+        };
+        let lines = source.read().map_err(|err| RunError::Input {
+            day,
+            source: source.clone(),
+            err,
+        })?;
+        Ok(DayInput {
+            registration,
+            source,
+            lines,
+        })
+    }
+
+    fn parse(&self) -> Result<(Box<dyn Solution>, Duration), RunError> {
+        let (parsed, elapsed) = timing::time(|| (self.registration.parse)(&self.lines));
+        let solution = parsed.map_err(|err| RunError::Parse {
+            source: self.source.clone(),
+            err,
+        })?;
+        Ok((solution, elapsed))
+    }
+}
+
+/// One part's answer, or `None` for a part the day doesn't solve
+fn solve(solution: &dyn Solution, day: u32, part: u32) -> Result<Option<Answer>, RunError> {
+    let answer = solution.solve(part).transpose();
+    answer.map_err(|err| RunError::Solve { day, part, err })
+}
+
+/// Returns false if a day failed to load or solve in json or csv output
+pub fn run(args: &RunArgs) -> Result<bool, RunError> {
+    if args.format != Format::Text {
+        return run_records(args);
+    }
+    let mut table = Table::new(&["day", "part", "answer", "parse", "solve"]);
+
+    for day in &args.selection.days {
+        let (solution, parse_time) = DayInput::read(args, *day)?.parse()?;
+        let mut parse_cell = format_duration(parse_time);
+
+        for part in &args.selection.parts {
+            let (answer, solve_time) = timing::time(|| solve(&*solution, *day, *part));
+            let Some(answer) = answer? else { continue };
+
+            if args.time {
+                table.add_row(vec![
+                    day.to_string(),
+                    part.to_string(),
+                    answer.to_string(),
+                    std::mem::take(&mut parse_cell),
+                    format_duration(solve_time),
+                ]);
+            } else {
+                println!("Day {} part {}: {}", day, part, answer);
+            }
+        }
+    }
+
+    if args.time {
+        print!("{}", table);
+    }
+    Ok(true)
+}
+
+/// Errors become records rather than ending the run, so one bad input
+/// doesn't hide the results for every other day
+fn run_records(args: &RunArgs) -> Result<bool, RunError> {
+    let mut records = Vec::new();
+
+    for day in &args.selection.days {
+        match DayInput::read(args, *day).and_then(|input| input.parse()) {
+            Ok((solution, parse_time)) => {
+                for part in &args.selection.parts {
+                    let (answer, solve_time) = timing::time(|| solution.solve(*part));
+                    match answer {
+                        Some(Ok(answer)) => records
+                            .push(Record::solved(*day, *part, answer, parse_time, solve_time)),
+                        Some(Err(err)) => {
+                            records.push(Record::failed(*day, *part, err.to_string()))
+                        }
+                        None => {}
+                    }
+                }
+            }
+            Err(err) => {
+                let registration =
+                    solution::for_day(*day).expect("cli only selects registered days");
+                for part in args
+                    .selection
+                    .parts
+                    .iter()
+                    .filter(|part| **part <= registration.parts)
+                {
+                    records.push(Record::failed(*day, *part, err.to_string()));
+                }
+            }
+        }
+    }
+
+    match args.format {
+        Format::Json => print!("{}", record::to_json(&records)),
+        Format::Csv => print!("{}", record::to_csv(&records)),
+        Format::Text => unreachable!("text output is written as it goes"),
+    }
+    Ok(records.iter().all(|record| record.error.is_none()))
+}
+
+pub fn bench(args: &BenchArgs) -> Result<(), RunError> {
+    let mut table = Table::new(&["day", "part", "runs", "min", "median", "mean"]);
+    let mut add_row = |day: u32, part: String, stats: Stats| {
+        table.add_row(vec![
+            day.to_string(),
+            part,
+            stats.runs.to_string(),
+            format_duration(stats.min),
+            format_duration(stats.median),
+            format_duration(stats.mean),
+        ])
+    };
+
+    for day in &args.run.selection.days {
+        let input = DayInput::read(&args.run, *day)?;
+        let (solution, _) = input.parse()?;
+
+        let stats = repeat(args, || {
+            black_box(input.parse().ok());
+        });
+        add_row(*day, "parse".to_string(), stats);
+
+        for part in &args.run.selection.parts {
+            if solve(&*solution, *day, *part)?.is_none() {
+                continue;
+            }
+            let stats = repeat(args, || {
+                black_box(solution.solve(*part));
+            });
+            add_row(*day, part.to_string(), stats);
+        }
+    }
+
+    print!("{}", table);
+    Ok(())
+}
+
+/// Check every selected part against the answers file, or with `--record`
+/// overwrite the expected answers. Returns false if any answer is wrong.
+pub fn verify(args: &VerifyArgs) -> Result<bool, RunError> {
+    let answers_error = |err| RunError::Answers {
+        path: args.answers.clone(),
+        err,
+    };
+    let mut answers = Answers::load(&args.answers).map_err(answers_error)?;
+    let mut table = Table::new(&["day", "part", "answer", "expected", "result"]);
+    let mut all_passed = true;
+
+    for day in &args.run.selection.days {
+        let (solution, _) = DayInput::read(&args.run, *day)?.parse()?;
+
+        for part in &args.run.selection.parts {
+            let Some(answer) = solve(&*solution, *day, *part)? else {
+                continue;
+            };
+            let (expected, result) = if args.record {
+                answers.record(*day, *part, answer.clone());
+                (answer.to_string(), "recorded")
+            } else {
+                match answers.check(*day, *part, &answer) {
+                    Verdict::Pass => (answer.to_string(), "pass"),
+                    Verdict::Fail(expected) => {
+                        all_passed = false;
+                        (expected.to_string(), "FAIL")
+                    }
+                    Verdict::Unknown => ("?".to_string(), "unknown"),
+                }
+            };
+            table.add_row(vec![
+                day.to_string(),
+                part.to_string(),
+                answer.to_string(),
+                expected,
+                result.to_string(),
+            ]);
+        }
+    }
+
+    print!("{}", table);
+    if args.record {
+        answers.save(&args.answers).map_err(answers_error)?;
+        println!("recorded answers in {}", args.answers.display());
+    }
+    Ok(all_passed)
+}
+
+pub fn fetch(args: &FetchArgs) -> Result<(), RunError> {
+    for day in &args.days {
+        let fetched =
+            fetch::fetch_input(&args.base_url, &fetch::session_token, &args.input_dir, *day)
+                .map_err(|err| RunError::Fetch { day: *day, err })?;
+
+        match fetched {
+            Fetched::Downloaded(path) => println!("Day {}: downloaded {}", day, path.display()),
+            Fetched::Cached(path) => println!("Day {}: already have {}", day, path.display()),
+        }
+    }
+    Ok(())
+}
+
+pub fn examples(args: &ExamplesArgs) -> Result<(), RunError> {
+    let html = std::fs::read_to_string(&args.page).map_err(|err| RunError::Examples {
+        path: args.page.clone(),
+        err,
+    })?;
+    let page = examples::extract(&html);
+    if page.examples.is_empty() {
+        println!("No examples found in {}", args.page.display());
+        return Ok(());
+    }
+
+    let written =
+        examples::save(&page, &args.fixtures_dir, args.day).map_err(|err| RunError::Examples {
+            path: args.fixtures_dir.clone(),
+            err,
+        })?;
+    for path in written {
+        println!("Day {}: wrote {}", args.day, path.display());
+    }
+    Ok(())
+}
+
+/// Print the day 1 sonar analysis. Positions count depths from 1, which
+/// are line numbers for the usual one depth per line.
+pub fn analyze(args: &AnalyzeArgs) -> Result<(), RunError> {
+    let lines = args.input.read().map_err(|err| RunError::Input {
+        day: 1,
+        source: args.input.clone(),
+        err,
+    })?;
+    let report = day1::parse_report(&lines).map_err(|err| RunError::Parse {
+        source: args.input.clone(),
+        err,
+    })?;
+    let analysis = day1::analyze(&report, &args.options);
+
+    println!("depths:            {}", analysis.depths);
+    println!("increases:         {}", analysis.increases);
+    println!("window increases:  {}", analysis.window_increases);
+    match analysis.longest_rise {
+        Some(rise) => println!(
+            "longest rise:      {} depths from position {}",
+            rise.length,
+            rise.start + 1
+        ),
+        None => println!("longest rise:      none"),
+    }
+    match analysis
+        .plateaus
+        .iter()
+        .max_by_key(|p| (p.length, Reverse(p.start)))
+    {
+        Some(longest) => println!(
+            "plateaus:          {} (longest {} depths from position {})",
+            analysis.plateaus.len(),
+            longest.length,
+            longest.start + 1
+        ),
+        None => println!("plateaus:          none"),
+    }
+    match analysis.largest_drop {
+        Some(drop) => println!(
+            "largest drop:      {} at position {} ({} to {})",
+            drop.size(),
+            drop.index + 1,
+            drop.from,
+            drop.to
+        ),
+        None => println!("largest drop:      none"),
+    }
+    println!(
+        "outliers:          {} (|z| > {} over the previous {} depths)",
+        analysis.outliers.len(),
+        args.options.threshold,
+        args.options.window
+    );
+
+    if !analysis.outliers.is_empty() {
+        let mut table = Table::new(&["position", "depth", "z-score"]);
+        for outlier in &analysis.outliers {
+            table.add_row(vec![
+                (outlier.index + 1).to_string(),
+                outlier.depth.to_string(),
+                format!("{:.2}", outlier.z_score),
+            ]);
+        }
+        println!();
+        print!("{}", table);
+    }
+    Ok(())
+}
+
+/// Run `f` for the warm-up count, then time it for the iteration count
+fn repeat(args: &BenchArgs, mut f: impl FnMut()) -> Stats {
+    for _ in 0..args.warmup {
+        f();
+    }
+    let samples = (0..args.iterations)
+        .map(|_| timing::time(&mut f).1)
+        .collect::<Vec<Duration>>();
+    Stats::from_samples(&samples)
+}
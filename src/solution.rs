@@ -0,0 +1,178 @@
+//! A common shape for every day's puzzle
+//!
+//! Each dayN module parses its input into a type implementing `Solution`,
+//! and registers that type in `SOLUTIONS` so the runner can treat every
+//! day the same way.
+use std::error::Error;
+use std::fmt;
+
+use crate::parse::ParseResult;
+use crate::{day1, day2, day3, day4, day5, day6, day7, day8};
+
+/// The answer to one part of a puzzle
+#[derive(Debug, PartialEq, Clone)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(n: i32) -> Self {
+        Answer::Number(n.into())
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(n: i64) -> Self {
+        Answer::Number(n)
+    }
+}
+
+macro_rules! answer_try_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl TryFrom<$t> for Answer {
+                type Error = SolveError;
+
+                fn try_from(n: $t) -> SolveResult {
+                    i64::try_from(n)
+                        .map(Answer::Number)
+                        .map_err(|_| SolveError::TooLarge(n.to_string()))
+                }
+            }
+        )*
+    };
+}
+
+answer_try_from_int!(isize, usize, u64);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+/// Why an input that parsed has no answer
+#[derive(Debug, PartialEq, Clone)]
+pub enum SolveError {
+    /// The puzzle can't be finished, for the given reason
+    NoAnswer(String),
+    /// The answer, which doesn't fit in an `Answer::Number`
+    TooLarge(String),
+}
+
+pub type SolveResult = Result<Answer, SolveError>;
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::NoAnswer(reason) => write!(f, "no answer: {}", reason),
+            SolveError::TooLarge(n) => write!(f, "answer {} does not fit in 64 bits", n),
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+pub trait Solution {
+    /// Build the puzzle from its input lines
+    fn parse(input: &[String]) -> ParseResult<Self>
+    where
+        Self: Sized;
+
+    fn part1(&self) -> SolveResult;
+
+    /// Days without a second part yet leave this as `None`
+    fn part2(&self) -> Option<SolveResult> {
+        None
+    }
+
+    fn solve(&self, part: u32) -> Option<SolveResult> {
+        match part {
+            1 => Some(self.part1()),
+            2 => self.part2(),
+            _ => None,
+        }
+    }
+}
+
+pub type ParseFn = fn(&[String]) -> ParseResult<Box<dyn Solution>>;
+
+/// An entry in the table of days the runner knows about
+pub struct Registration {
+    pub day: u32,
+    /// How many parts the day solves, so a failed input is only reported
+    /// against parts that exist
+    pub parts: u32,
+    pub parse: ParseFn,
+}
+
+fn parse_boxed<S: Solution + 'static>(input: &[String]) -> ParseResult<Box<dyn Solution>> {
+    Ok(Box::new(S::parse(input)?))
+}
+
+const fn register<S: Solution + 'static>(day: u32, parts: u32) -> Registration {
+    Registration {
+        day,
+        parts,
+        parse: parse_boxed::<S>,
+    }
+}
+
+pub const SOLUTIONS: &[Registration] = &[
+    register::<day1::Day1>(1, 2),
+    register::<day2::Day2>(2, 2),
+    register::<day3::Day3>(3, 2),
+    register::<day4::Day4>(4, 2),
+    register::<day5::Day5>(5, 2),
+    register::<day6::Day6>(6, 2),
+    register::<day7::Day7>(7, 2),
+    register::<day8::Day8>(8, 1),
+];
+
+pub fn for_day(day: u32) -> Option<&'static Registration> {
+    SOLUTIONS.iter().find(|s| s.day == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers_days_in_order() {
+        let days = SOLUTIONS.iter().map(|s| s.day).collect::<Vec<u32>>();
+        assert_eq!((1..=8).collect::<Vec<u32>>(), days);
+    }
+
+    #[test]
+    fn finds_registered_day() {
+        assert_eq!(Some(3), for_day(3).map(|s| s.day));
+        assert!(for_day(25).is_none());
+    }
+
+    #[test]
+    fn displays_answers() {
+        assert_eq!("1393", Answer::from(1393).to_string());
+        assert_eq!("10111", Answer::from(String::from("10111")).to_string());
+    }
+
+    #[test]
+    fn rejects_answers_too_large_for_a_number() {
+        assert_eq!(Ok(Answer::Number(7)), Answer::try_from(7usize));
+        let err = Answer::try_from(u64::MAX).unwrap_err();
+        assert_eq!(SolveError::TooLarge(u64::MAX.to_string()), err);
+        assert_eq!(
+            "answer 18446744073709551615 does not fit in 64 bits",
+            err.to_string()
+        );
+    }
+}
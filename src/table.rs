@@ -0,0 +1,81 @@
+//! Plain-text tables for runner output
+use std::fmt;
+
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Table {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn add_row(&mut self, row: Vec<String>) {
+        assert_eq!(
+            self.headers.len(),
+            row.len(),
+            "row has wrong number of cells"
+        );
+        self.rows.push(row);
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        (0..self.headers.len())
+            .map(|col| {
+                self.rows
+                    .iter()
+                    .chain(std::iter::once(&self.headers))
+                    .map(|row| row[col].chars().count())
+                    .max()
+                    .unwrap()
+            })
+            .collect()
+    }
+}
+
+/// Cells are right-aligned so numbers and durations line up
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let widths = self.widths();
+        let write_row = |f: &mut fmt::Formatter, row: &[String]| {
+            let cells = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:>w$}", cell, w = width))
+                .collect::<Vec<String>>();
+            writeln!(f, "{}", cells.join("  ").trim_end())
+        };
+
+        write_row(f, &self.headers)?;
+        let rule = widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<String>>();
+        write_row(f, &rule)?;
+        for row in &self.rows {
+            write_row(f, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligns_columns() {
+        let mut table = Table::new(&["day", "answer"]);
+        table.add_row(vec!["1".to_string(), "1393".to_string()]);
+        table.add_row(vec!["12".to_string(), "7".to_string()]);
+
+        assert_eq!(
+            "day  answer\n---  ------\n  1    1393\n 12       7\n",
+            table.to_string()
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Stopwatch and summary statistics for comparing solution approaches
+use std::time::{Duration, Instant};
+
+/// Run `f`, returning its result and how long it took
+pub fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        assert!(!samples.is_empty(), "need at least one sample");
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+
+        let mid = sorted.len() / 2;
+        let median = if sorted.len() % 2 == 0 {
+            (sorted[mid - 1] + sorted[mid]) / 2
+        } else {
+            sorted[mid]
+        };
+        let total: Duration = sorted.iter().sum();
+
+        Stats {
+            runs: sorted.len(),
+            min: sorted[0],
+            median,
+            mean: total / u32::try_from(sorted.len()).unwrap(),
+        }
+    }
+}
+
+/// Call `f` once to warm up, then time `runs` more calls
+pub fn measure(runs: usize, mut f: impl FnMut()) -> Stats {
+    f();
+    let samples = (0..runs).map(|_| time(&mut f).1).collect::<Vec<_>>();
+    Stats::from_samples(&samples)
+}
+
+/// Format a duration in the largest unit that keeps it above 1, e.g. 1.234ms
+pub fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    if nanos < 1_000 {
+        format!("{}ns", nanos)
+    } else if nanos < 1_000_000 {
+        format!("{:.1}µs", nanos as f64 / 1e3)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.3}ms", nanos as f64 / 1e6)
+    } else {
+        format!("{:.3}s", nanos as f64 / 1e9)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn micros(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|v| Duration::from_micros(*v)).collect()
+    }
+
+    #[test]
+    fn summarises_odd_number_of_samples() {
+        let stats = Stats::from_samples(&micros(&[30, 10, 20, 100, 40]));
+        assert_eq!(
+            Stats {
+                runs: 5,
+                min: Duration::from_micros(10),
+                median: Duration::from_micros(30),
+                mean: Duration::from_micros(40),
+            },
+            stats
+        );
+    }
+
+    #[test]
+    fn takes_middle_pair_for_even_median() {
+        let stats = Stats::from_samples(&micros(&[40, 10, 20, 30]));
+        assert_eq!(Duration::from_micros(25), stats.median);
+    }
+
+    #[test]
+    fn measures_after_a_warm_up_call() {
+        let mut calls = 0;
+        let stats = measure(3, || calls += 1);
+        assert_eq!((4, 3), (calls, stats.runs));
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!("999ns", format_duration(Duration::from_nanos(999)));
+        assert_eq!("12.3µs", format_duration(Duration::from_nanos(12_345)));
+        assert_eq!("1.235ms", format_duration(Duration::from_micros(1_235)));
+        assert_eq!("2.500s", format_duration(Duration::from_millis(2_500)));
+    }
+}
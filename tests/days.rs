@@ -0,0 +1,91 @@
+//! The day modules used as a library, the way other tools call them
+use advent2021::day3::part1::calculate_rates;
+use advent2021::day3::part2::{life_support_rating, o2_generator_rating, BitNode};
+use advent2021::day5::VentsMap;
+use advent2021::day6::School;
+use advent2021::{day1, day2, day7};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+fn strings(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+fn diagnostic_report() -> Vec<String> {
+    strings(&[
+        "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000", "11001",
+        "00010", "01010",
+    ])
+}
+
+#[test]
+fn counts_sonar_increases() {
+    let report = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+    assert_eq!(7, day1::count_increases(report));
+    assert_eq!(5, day1::count_increases(day1::sum_triples(&report)));
+}
+
+#[test]
+fn sweeps_sonar_from_a_file() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    let path = advent2021::input::path_for_day(&dir, 1);
+    let reader = BufReader::new(File::open(path).unwrap());
+
+    let sweep = day1::stream::sweep(reader, 3).unwrap();
+    assert_eq!((1393, 1359), (sweep.increases, sweep.window_increases));
+}
+
+#[test]
+fn navigates_submarine() {
+    let steps = strings(&[
+        "forward 5",
+        "down 5",
+        "forward 8",
+        "up 3",
+        "down 8",
+        "forward 2",
+    ]);
+    let commands = day2::parse_commands(&steps).unwrap();
+    let position = day2::navigate(commands.iter().copied());
+    assert_eq!((15, 10), (position.horizontal, position.depth));
+    assert_eq!(900, day2::navigate_with_aim(commands).product());
+}
+
+#[test]
+fn reports_parse_errors_through_the_api() {
+    let err = day2::parse_commands(&strings(&["forward 5", "sideways 2"])).unwrap_err();
+    assert_eq!(2, err.line);
+}
+
+#[test]
+fn rates_diagnostics_with_a_bit_tree() {
+    let report = diagnostic_report();
+    assert_eq!(Some(198), calculate_rates(&report).product().to_u64());
+
+    let tree = BitNode::from(&report);
+    assert_eq!(12, tree.count);
+    assert_eq!(7, tree.get(1).count);
+    assert_eq!("10111", o2_generator_rating(&tree, 5));
+    assert_eq!("230", life_support_rating(&tree, 5).to_string());
+}
+
+#[test]
+fn maps_vents_from_segments() {
+    let segments = [((0, 9), (5, 9)), ((0, 9), (2, 9)), ((3, 4), (1, 4))];
+    let vents = VentsMap::from_segments(&segments, false);
+    assert_eq!(3, vents.count_dangerous_areas());
+}
+
+#[test]
+fn grows_a_school_of_lanternfish() {
+    let mut school = School::from_string("3,4,3,1,2").unwrap();
+    assert_eq!(26, school.to_day(18).count());
+    assert_eq!(5934, school.to_day(80).count());
+}
+
+#[test]
+fn finds_cheapest_crab_alignment() {
+    let histogram = day7::histogram("16,1,2,0,4,2,7,1,2,14").unwrap();
+    assert_eq!((2, 37), day7::linear_least_cost(&histogram));
+}
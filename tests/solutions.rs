@@ -0,0 +1,60 @@
+//! Every registered solution, driven the way the runner drives them
+use advent2021::answers::Verdict;
+use advent2021::examples;
+use advent2021::solution::{self, Answer};
+
+#[test]
+fn registers_days_in_order() {
+    let days: Vec<u32> = solution::SOLUTIONS.iter().map(|r| r.day).collect();
+    assert_eq!((1..=8).collect::<Vec<u32>>(), days);
+    assert!(solution::for_day(26).is_none());
+}
+
+#[test]
+fn solves_every_example_fixture() {
+    for registration in solution::SOLUTIONS {
+        let fixtures = examples::default_fixtures_dir();
+        let (input, answers) = examples::load(&fixtures, registration.day).unwrap();
+
+        let solution = (registration.parse)(&input).unwrap();
+        for part in 1..=2 {
+            let answer = solution.solve(part);
+            assert_eq!(
+                part <= registration.parts,
+                answer.is_some(),
+                "day {} part {}",
+                registration.day,
+                part
+            );
+            if let Some(answer) = answer {
+                assert_eq!(
+                    Verdict::Pass,
+                    answers.check(registration.day, part, &answer.unwrap()),
+                    "day {} part {}",
+                    registration.day,
+                    part
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn reports_where_bad_input_goes_wrong() {
+    let registration = solution::for_day(1).unwrap();
+    let input = vec!["199".to_string(), "2OO".to_string()];
+
+    let err = (registration.parse)(&input).err().unwrap();
+    assert_eq!((1, 2, 1), (err.day, err.line, err.column));
+}
+
+#[test]
+fn solves_from_a_library_caller() {
+    let input: Vec<String> = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263"
+        .lines()
+        .map(String::from)
+        .collect();
+    let solution = (solution::for_day(1).unwrap().parse)(&input).unwrap();
+    assert_eq!(Ok(Answer::Number(7)), solution.part1());
+    assert_eq!(Some(Ok(Answer::Number(5))), solution.part2());
+}